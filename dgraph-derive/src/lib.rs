@@ -0,0 +1,427 @@
+//! `#[derive(DgraphNode)]`, re-exported by the `dgraph` crate with its `derive` feature.
+//!
+//! The struct is read once to generate the node's `dgraph.type`, its predicate schema, a
+//! DQL selection set and its mutation JSON. Attributes:
+//!
+//! * on the struct: `#[dgraph(name = "Person")]` sets the `dgraph.type`, which defaults to
+//!   the struct name.
+//! * on fields:
+//!   * `predicate = "..."` names the predicate, which defaults to the field name.
+//!   * `uid` marks the field holding the node's UID. A field named `uid` is picked up
+//!     without it.
+//!   * `edge` marks a field whose type, or its `Option`/`Vec` element, is itself a
+//!     `DgraphNode`.
+//!   * `schema_type = "geo"` overrides the schema type inferred from the Rust type.
+//!   * `index(exact, term)`, `reverse`, `count`, `upsert` and `lang` add the matching
+//!     schema directives.
+//!   * `skip` leaves the field out entirely.
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, Attribute, Data, DeriveInput, Fields, GenericArgument, Ident, Lit, Meta,
+    NestedMeta, PathArguments, Type,
+};
+
+#[proc_macro_derive(DgraphNode, attributes(dgraph))]
+pub fn derive_dgraph_node(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+#[derive(Default)]
+struct FieldAttrs {
+    predicate: Option<String>,
+    schema_type: Option<String>,
+    index: Vec<String>,
+    reverse: bool,
+    count: bool,
+    upsert: bool,
+    lang: bool,
+    uid: bool,
+    edge: bool,
+    skip: bool,
+}
+
+/// How a field's type wraps the value it holds: `T`, `Option<T>`, `Vec<T>` or
+/// `Option<Vec<T>>`.
+struct Shape<'a> {
+    optional: bool,
+    list: bool,
+    inner: &'a Type,
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "DgraphNode can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                ident,
+                "DgraphNode can only be derived for structs",
+            ))
+        }
+    };
+
+    let type_name = node_name(&input.attrs)?.unwrap_or_else(|| ident.to_string());
+
+    let mut schema = String::new();
+    let mut type_fields = Vec::new();
+    let mut selection = Vec::new();
+    let mut mutation = Vec::new();
+
+    for field in fields {
+        let field_ident = field.ident.as_ref().expect("named field");
+        let attrs = field_attrs(&field.attrs)?;
+        if attrs.skip {
+            continue;
+        }
+
+        let name = field_ident.to_string();
+        let name = name.trim_start_matches("r#");
+
+        if attrs.uid || name == "uid" {
+            selection.push(select(name, "uid", None));
+            mutation.push(quote! {
+                ::dgraph::__private::insert_uid(
+                    &mut object,
+                    ::dgraph::__private::to_json(&self.#field_ident)?,
+                );
+            });
+            continue;
+        }
+
+        let predicate = attrs.predicate.clone().unwrap_or_else(|| name.to_string());
+        let shape = shape(&field.ty);
+
+        schema.push_str(&predicate_schema(&predicate, &attrs, &shape, field_ident)?);
+        type_fields.push(predicate.clone());
+
+        if attrs.edge {
+            selection.push(select(name, &predicate, Some(shape.inner)));
+            mutation.push(edge_mutation(field_ident, &predicate, &shape));
+        } else {
+            selection.push(select(name, &predicate, None));
+            mutation.push(quote! {
+                ::dgraph::__private::insert(
+                    &mut object,
+                    #predicate,
+                    ::dgraph::__private::to_json(&self.#field_ident)?,
+                );
+            });
+        }
+    }
+
+    schema.push_str(&format!("type {} {{\n", type_name));
+    for predicate in &type_fields {
+        schema.push_str(&format!("  {}\n", predicate));
+    }
+    schema.push_str("}\n");
+
+    Ok(quote! {
+        impl #impl_generics ::dgraph::DgraphNode for #ident #ty_generics #where_clause {
+            const TYPE_NAME: &'static str = #type_name;
+
+            fn schema() -> ::std::string::String {
+                #schema.to_string()
+            }
+
+            #[allow(unused_mut, unused_variables)]
+            fn selection_to_depth(depth: usize) -> ::std::string::String {
+                let mut fields: ::std::vec::Vec<::std::string::String> = ::std::vec::Vec::new();
+                #(#selection)*
+                format!("{{ {} }}", fields.join(" "))
+            }
+
+            fn to_mutation_json(
+                &self,
+            ) -> ::std::result::Result<::dgraph::__private::Value, ::dgraph::DgraphError> {
+                let mut object = ::dgraph::__private::Map::new();
+                object.insert(
+                    "dgraph.type".to_string(),
+                    ::dgraph::__private::Value::from(#type_name),
+                );
+                #(#mutation)*
+                Ok(::dgraph::__private::Value::Object(object))
+            }
+        }
+    })
+}
+
+/// Pushes one entry of the selection set, aliased to the field name when the predicate is
+/// named differently so the query result deserializes into the struct. Edges are expanded
+/// until `depth` runs out.
+fn select(name: &str, predicate: &str, edge: Option<&Type>) -> TokenStream2 {
+    let entry = if name == predicate {
+        predicate.to_string()
+    } else {
+        format!("{}: {}", name, predicate)
+    };
+
+    match edge {
+        Some(inner) => quote! {
+            if depth > 0 {
+                fields.push(format!(
+                    "{} {}",
+                    #entry,
+                    <#inner as ::dgraph::DgraphNode>::selection_to_depth(depth - 1)
+                ));
+            }
+        },
+        None => quote! {
+            fields.push(#entry.to_string());
+        },
+    }
+}
+
+fn edge_mutation(field: &Ident, predicate: &str, shape: &Shape) -> TokenStream2 {
+    match (shape.optional, shape.list) {
+        (false, false) => quote! {
+            ::dgraph::__private::insert(
+                &mut object,
+                #predicate,
+                ::dgraph::DgraphNode::to_mutation_json(&self.#field)?,
+            );
+        },
+        (false, true) => quote! {
+            ::dgraph::__private::insert(
+                &mut object,
+                #predicate,
+                ::dgraph::__private::edges(self.#field.iter())?,
+            );
+        },
+        (true, false) => quote! {
+            if let Some(node) = &self.#field {
+                ::dgraph::__private::insert(
+                    &mut object,
+                    #predicate,
+                    ::dgraph::DgraphNode::to_mutation_json(node)?,
+                );
+            }
+        },
+        (true, true) => quote! {
+            if let Some(nodes) = &self.#field {
+                ::dgraph::__private::insert(
+                    &mut object,
+                    #predicate,
+                    ::dgraph::__private::edges(nodes.iter())?,
+                );
+            }
+        },
+    }
+}
+
+/// Renders the schema line of a predicate, such as `name: string @index(exact) .`.
+fn predicate_schema(
+    predicate: &str,
+    attrs: &FieldAttrs,
+    shape: &Shape,
+    field: &Ident,
+) -> syn::Result<String> {
+    let scalar = match (&attrs.schema_type, attrs.edge) {
+        (Some(schema_type), _) => schema_type.clone(),
+        (None, true) => "uid".to_string(),
+        (None, false) => match scalar_type(shape.inner) {
+            Some(scalar) => scalar.to_string(),
+            None => {
+                return Err(syn::Error::new_spanned(
+                    field,
+                    "cannot infer the schema type of this field; \
+                     add #[dgraph(schema_type = \"...\")] or #[dgraph(edge)]",
+                ))
+            }
+        },
+    };
+
+    let mut line = if shape.list {
+        format!("{}: [{}]", predicate, scalar)
+    } else {
+        format!("{}: {}", predicate, scalar)
+    };
+
+    if !attrs.index.is_empty() {
+        line.push_str(&format!(" @index({})", attrs.index.join(", ")));
+    }
+    if attrs.reverse {
+        line.push_str(" @reverse");
+    }
+    if attrs.count {
+        line.push_str(" @count");
+    }
+    if attrs.upsert {
+        line.push_str(" @upsert");
+    }
+    if attrs.lang {
+        line.push_str(" @lang");
+    }
+    line.push_str(" .\n");
+
+    Ok(line)
+}
+
+fn scalar_type(ty: &Type) -> Option<&'static str> {
+    let ident = match ty {
+        Type::Path(path) => path.path.segments.last()?.ident.to_string(),
+        Type::Reference(reference) => return scalar_type(&reference.elem),
+        _ => return None,
+    };
+
+    match ident.as_str() {
+        "String" | "str" | "char" => Some("string"),
+        "i8" | "i16" | "i32" | "i64" | "isize" | "u8" | "u16" | "u32" | "u64" | "usize" => {
+            Some("int")
+        }
+        "f32" | "f64" => Some("float"),
+        "bool" => Some("bool"),
+        "Uid" => Some("uid"),
+        "DateTime" | "NaiveDateTime" | "NaiveDate" | "SystemTime" => Some("datetime"),
+        _ => None,
+    }
+}
+
+fn shape(ty: &Type) -> Shape<'_> {
+    let (optional, ty) = match generic_inner(ty, "Option") {
+        Some(inner) => (true, inner),
+        None => (false, ty),
+    };
+    let (list, inner) = match generic_inner(ty, "Vec") {
+        Some(inner) => (true, inner),
+        None => (false, ty),
+    };
+
+    Shape {
+        optional,
+        list,
+        inner,
+    }
+}
+
+/// Returns `T` if `ty` is `wrapper<T>`.
+fn generic_inner<'a>(ty: &'a Type, wrapper: &str) -> Option<&'a Type> {
+    let segment = match ty {
+        Type::Path(path) => path.path.segments.last()?,
+        _ => return None,
+    };
+
+    if segment.ident != wrapper {
+        return None;
+    }
+
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) => match args.args.first()? {
+            GenericArgument::Type(inner) => Some(inner),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn node_name(attrs: &[Attribute]) -> syn::Result<Option<String>> {
+    let mut name = None;
+
+    for meta in dgraph_metas(attrs)? {
+        match meta {
+            NestedMeta::Meta(Meta::NameValue(value)) if value.path.is_ident("name") => {
+                name = Some(string_value(&value.lit)?);
+            }
+            other => {
+                return Err(syn::Error::new_spanned(
+                    other,
+                    "unknown dgraph attribute, expected `name = \"...\"`",
+                ))
+            }
+        }
+    }
+
+    Ok(name)
+}
+
+fn field_attrs(attrs: &[Attribute]) -> syn::Result<FieldAttrs> {
+    let mut field = FieldAttrs::default();
+
+    for meta in dgraph_metas(attrs)? {
+        match meta {
+            NestedMeta::Meta(Meta::Path(path)) => {
+                let flag = match path.get_ident() {
+                    Some(flag) => flag.to_string(),
+                    None => String::new(),
+                };
+
+                match flag.as_str() {
+                    "reverse" => field.reverse = true,
+                    "count" => field.count = true,
+                    "upsert" => field.upsert = true,
+                    "lang" => field.lang = true,
+                    "uid" => field.uid = true,
+                    "edge" => field.edge = true,
+                    "skip" => field.skip = true,
+                    _ => return Err(syn::Error::new_spanned(path, "unknown dgraph attribute")),
+                }
+            }
+            NestedMeta::Meta(Meta::NameValue(value)) if value.path.is_ident("predicate") => {
+                field.predicate = Some(string_value(&value.lit)?);
+            }
+            NestedMeta::Meta(Meta::NameValue(value)) if value.path.is_ident("schema_type") => {
+                field.schema_type = Some(string_value(&value.lit)?);
+            }
+            NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("index") => {
+                for tokenizer in list.nested {
+                    let name = match &tokenizer {
+                        NestedMeta::Meta(Meta::Path(path)) => path.get_ident(),
+                        _ => None,
+                    };
+
+                    match name {
+                        Some(name) => field.index.push(name.to_string()),
+                        None => {
+                            return Err(syn::Error::new_spanned(
+                                tokenizer,
+                                "expected a tokenizer name",
+                            ))
+                        }
+                    }
+                }
+            }
+            other => return Err(syn::Error::new_spanned(other, "unknown dgraph attribute")),
+        }
+    }
+
+    Ok(field)
+}
+
+fn dgraph_metas(attrs: &[Attribute]) -> syn::Result<Vec<NestedMeta>> {
+    let mut metas = Vec::new();
+
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("dgraph")) {
+        match attr.parse_meta()? {
+            Meta::List(list) => metas.extend(list.nested),
+            other => return Err(syn::Error::new_spanned(other, "expected #[dgraph(...)]")),
+        }
+    }
+
+    Ok(metas)
+}
+
+fn string_value(lit: &Lit) -> syn::Result<String> {
+    match lit {
+        Lit::Str(value) => Ok(value.value()),
+        other => Err(syn::Error::new_spanned(other, "expected a string literal")),
+    }
+}
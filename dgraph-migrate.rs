@@ -0,0 +1,107 @@
+use std::env;
+use std::error::Error;
+use std::process;
+
+use dgraph::migrate::Migrator;
+use dgraph::Dgraph;
+
+const USAGE: &str = "\
+Usage: dgraph-migrate [OPTIONS] <status|up>
+
+Applies the migrations in a directory, named `<version>_<name>.(schema|rdf|json)`.
+
+Options:
+    --addr <HOST:PORT>     Alpha to connect to [default: localhost:9080]
+    --dir <DIR>            Directory holding the migrations [default: migrations]
+    --user <USER>          ACL user to log in as
+    --password <PASSWORD>  ACL password";
+
+struct Args {
+    addr: String,
+    dir: String,
+    user: Option<String>,
+    password: Option<String>,
+    command: String,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        addr: "localhost:9080".to_string(),
+        dir: "migrations".to_string(),
+        user: None,
+        password: None,
+        command: String::new(),
+    };
+
+    let mut argv = env::args().skip(1);
+    while let Some(arg) = argv.next() {
+        let mut value = || {
+            argv.next()
+                .ok_or_else(|| format!("missing value for `{}`", arg))
+        };
+
+        match arg.as_str() {
+            "--addr" => args.addr = value()?,
+            "--dir" => args.dir = value()?,
+            "--user" => args.user = Some(value()?),
+            "--password" => args.password = Some(value()?),
+            "status" | "up" if args.command.is_empty() => args.command = arg.clone(),
+            _ => return Err(format!("unexpected argument `{}`", arg)),
+        }
+    }
+
+    if args.command.is_empty() {
+        return Err("missing command".to_string());
+    }
+
+    Ok(args)
+}
+
+fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    let mut builder = Dgraph::builder().endpoint(args.addr);
+    match (args.user, args.password) {
+        (Some(user), Some(password)) => builder = builder.login(user, password),
+        (None, None) => (),
+        _ => return Err("`--user` and `--password` must be given together".into()),
+    }
+
+    let dgraph = builder.build()?;
+    let migrator = Migrator::from_dir(&args.dir)?;
+
+    if args.command == "status" {
+        for applied in migrator.applied(&dgraph)? {
+            println!(
+                "applied  {:>6}  {}  ({})",
+                applied.version, applied.name, applied.applied_at
+            );
+        }
+        for pending in migrator.pending(&dgraph)? {
+            println!("pending  {:>6}  {}", pending.version(), pending.name());
+        }
+    } else {
+        let applied = migrator.run(&dgraph)?;
+        if applied.is_empty() {
+            println!("Nothing to apply");
+        }
+        for version in applied {
+            println!("Applied {}", version);
+        }
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("error: {}\n\n{}", err, USAGE);
+            process::exit(2);
+        }
+    };
+
+    if let Err(err) = run(args) {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }
+}
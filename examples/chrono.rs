@@ -59,7 +59,7 @@ fn main() {
         last_seen: chrono::Utc::now(),
     };
 
-    let mut txn = dgraph.new_txn();
+    let mut txn = dgraph.new_txn().expect("Failed to create transaction.");
     let mut mutation = dgraph::Mutation::new();
 
     mutation.set_set_json(serde_json::to_vec(&casey).expect("Failed to serialize JSON."));
@@ -81,6 +81,7 @@ fn main() {
 
     let resp = dgraph
         .new_readonly_txn()
+        .expect("Failed to create transaction.")
         .query_with_vars(&query, vars)
         .expect("query");
     let root: Root = serde_json::from_slice(&resp.json).expect("Failed to parse JSON.");
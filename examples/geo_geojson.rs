@@ -57,7 +57,7 @@ fn main() {
         location: geojson::Geometry::new(geojson::Value::Point(vec![48.148_16, 17.106_74])),
     };
 
-    let mut txn = dgraph.new_txn();
+    let mut txn = dgraph.new_txn().expect("Failed to create transaction.");
     let mut mutation = dgraph::Mutation::new();
 
     mutation.set_set_json(serde_json::to_vec(&city).expect("Failed to serialize JSON."));
@@ -79,6 +79,7 @@ fn main() {
 
     let resp = dgraph
         .new_readonly_txn()
+        .expect("Failed to create transaction.")
         .query_with_vars(&query, vars)
         .expect("query");
     let root: Root = serde_json::from_slice(&resp.json).expect("Failed to parse JSON.");
@@ -67,7 +67,7 @@ fn main() {
         },
     };
 
-    let mut txn = dgraph.new_txn();
+    let mut txn = dgraph.new_txn().expect("Failed to create transaction.");
     let mut mutation = dgraph::Mutation::new();
 
     mutation.set_set_json(serde_json::to_vec(&city).expect("Failed to serialize JSON."));
@@ -89,6 +89,7 @@ fn main() {
 
     let resp = dgraph
         .new_readonly_txn()
+        .expect("Failed to create transaction.")
         .query_with_vars(&query, vars)
         .expect("query");
     let root: Root = serde_json::from_slice(&resp.json).expect("Failed to parse JSON.");
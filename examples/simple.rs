@@ -1,14 +1,7 @@
-use std::collections::HashMap;
-
 use chrono::prelude::*;
 use dgraph::{make_dgraph, Dgraph};
 use serde_derive::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Default, Debug)]
-struct Root {
-    me: Vec<Person>,
-}
-
 #[derive(Serialize, Deserialize, Default, Debug)]
 struct School {
     name: String,
@@ -69,7 +62,7 @@ fn main() {
     drop_schema(&dgraph);
     set_schema(&dgraph);
 
-    let mut txn = dgraph.new_txn();
+    let mut txn = dgraph.new_txn().expect("Failed to create transaction.");
 
     // While setting an object if a struct has a Uid then its properties in
     // the graph are updated. Else a new node is created.
@@ -104,9 +97,7 @@ fn main() {
     };
 
     // Run mutation
-    let mut mutation = dgraph::Mutation::new();
-    mutation.set_set_json(serde_json::to_vec(&p).expect("Failed to serialize JSON."));
-    let assigned = txn.mutate(mutation).expect("Failed to create data.");
+    let assigned = txn.set_json(&p).expect("Failed to create data.");
 
     // Commit transaction
     txn.commit().expect("Failed to commit mutation");
@@ -135,14 +126,16 @@ fn main() {
         }
     }"#;
 
-    let mut vars = HashMap::new();
-    vars.insert("$a".to_string(), "Alice".to_string());
+    let vars = dgraph::Vars::new().string("$a", "Alice");
 
     let resp = dgraph
         .new_readonly_txn()
+        .expect("Failed to create transaction.")
         .query_with_vars(&query, vars)
         .expect("query");
-    let root: Root = serde_json::from_slice(&resp.json).expect("Failed to convert slice to JSON.");
+    let me: Vec<Person> = resp
+        .block_as("me")
+        .expect("Failed to deserialize response.");
 
-    println!("\nQuery result for `eq(name, Alice)`:\n\n{:#?}", root);
+    println!("\nQuery result for `eq(name, Alice)`:\n\n{:#?}", me);
 }
@@ -95,7 +95,7 @@ fn main() {
     drop_schema(&dgraph);
     set_schema(&dgraph);
 
-    let mut txn = dgraph.new_txn();
+    let mut txn = dgraph.new_txn().expect("Failed to create transaction.");
 
     // While setting an object if a struct has a Uid then its properties in
     // the graph are updated. Else a new node is created.
@@ -166,6 +166,7 @@ fn main() {
 
     let resp = dgraph
         .new_readonly_txn()
+        .expect("Failed to create transaction.")
         .query_with_vars(&query, vars)
         .expect("query");
     let root: Root = serde_json::from_slice(&resp.json).expect("Failed to convert slice to JSON.");
@@ -0,0 +1,311 @@
+use grpcio::CallOption;
+use rand::prelude::*;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::protos::api;
+use crate::protos::api_grpc;
+
+/// How long an endpoint is taken out of rotation after a call to it fails with `UNAVAILABLE`,
+/// unless a health probe brings it back earlier.
+pub const DEFAULT_EJECTION_TIME: Duration = Duration::from_secs(30);
+
+/// How long a health check probe waits for `CheckVersion`, unless the interval between
+/// probes is shorter.
+pub const DEFAULT_PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The state of a single endpoint, as seen by a `Balancer`.
+#[derive(Clone, Debug)]
+pub struct EndpointStats {
+    /// Position of the endpoint in the list of clients the `Dgraph` was created with.
+    pub index: usize,
+    /// Number of calls currently running against the endpoint.
+    pub in_flight: usize,
+}
+
+/// Strategy choosing the endpoint that serves the next transaction or operation.
+///
+/// Only healthy endpoints are offered as candidates. When every endpoint has been ejected,
+/// all of them are offered again, so a balancer always has at least one candidate.
+pub trait Balancer: Send + Sync {
+    /// Returns the position within `candidates` of the endpoint to use.
+    fn pick(&self, candidates: &[EndpointStats]) -> usize;
+}
+
+/// Cycles through the candidates in order.
+#[derive(Debug, Default)]
+pub struct RoundRobin {
+    next: AtomicUsize,
+}
+
+impl Balancer for RoundRobin {
+    fn pick(&self, candidates: &[EndpointStats]) -> usize {
+        self.next.fetch_add(1, Ordering::Relaxed) % candidates.len()
+    }
+}
+
+/// Picks a candidate uniformly at random.
+#[derive(Debug, Default)]
+pub struct Random;
+
+impl Balancer for Random {
+    fn pick(&self, candidates: &[EndpointStats]) -> usize {
+        thread_rng().gen_range(0, candidates.len())
+    }
+}
+
+/// Picks the candidate with the fewest calls in flight.
+#[derive(Debug, Default)]
+pub struct LeastInFlight;
+
+impl Balancer for LeastInFlight {
+    fn pick(&self, candidates: &[EndpointStats]) -> usize {
+        candidates
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, stats)| stats.in_flight)
+            .map_or(0, |(position, _)| position)
+    }
+}
+
+pub(crate) struct Endpoint {
+    pub(crate) client: api_grpc::DgraphClient,
+    in_flight: AtomicUsize,
+    ejected_until: Mutex<Option<Instant>>,
+    ejection_time: Duration,
+}
+
+/// Keeps an endpoint's in-flight count raised for as long as a call is running, including
+/// async calls whose future is dropped before completion.
+pub(crate) struct InFlight<'a>(&'a AtomicUsize);
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl Endpoint {
+    fn is_ejected(&self, now: Instant) -> bool {
+        match *self
+            .ejected_until
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+        {
+            Some(until) => until > now,
+            None => false,
+        }
+    }
+
+    fn set_ejected_until(&self, until: Option<Instant>) {
+        *self
+            .ejected_until
+            .lock()
+            .unwrap_or_else(|err| err.into_inner()) = until;
+    }
+
+    pub(crate) fn begin(&self) -> InFlight<'_> {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        InFlight(&self.in_flight)
+    }
+
+    /// Ejects the endpoint when a call to it failed because it is unreachable.
+    pub(crate) fn observe<T>(&self, result: &grpcio::Result<T>) {
+        if let Err(grpcio::Error::RpcFailure(status)) = result {
+            if status.status == grpcio::RpcStatusCode::UNAVAILABLE {
+                self.set_ejected_until(Some(Instant::now() + self.ejection_time));
+            }
+        }
+    }
+
+    pub(crate) fn query(
+        &self,
+        request: &api::Request,
+        opt: CallOption,
+    ) -> grpcio::Result<api::Response> {
+        let _in_flight = self.begin();
+        let result = self.client.query_opt(request, opt);
+        self.observe(&result);
+        result
+    }
+
+    pub(crate) fn alter(
+        &self,
+        op: &api::Operation,
+        opt: CallOption,
+    ) -> grpcio::Result<api::Payload> {
+        let _in_flight = self.begin();
+        let result = self.client.alter_opt(op, opt);
+        self.observe(&result);
+        result
+    }
+
+    pub(crate) fn commit_or_abort(
+        &self,
+        context: &api::TxnContext,
+        opt: CallOption,
+    ) -> grpcio::Result<api::TxnContext> {
+        let _in_flight = self.begin();
+        let result = self.client.commit_or_abort_opt(context, opt);
+        self.observe(&result);
+        result
+    }
+
+    pub(crate) fn login(
+        &self,
+        request: &api::LoginRequest,
+        opt: CallOption,
+    ) -> grpcio::Result<api::Response> {
+        let _in_flight = self.begin();
+        let result = self.client.login_opt(request, opt);
+        self.observe(&result);
+        result
+    }
+
+    pub(crate) async fn query_async(
+        &self,
+        request: &api::Request,
+        opt: CallOption,
+    ) -> grpcio::Result<api::Response> {
+        let _in_flight = self.begin();
+        let result = match self.client.query_async_opt(request, opt) {
+            Ok(receiver) => receiver.await,
+            Err(err) => Err(err),
+        };
+        self.observe(&result);
+        result
+    }
+
+    pub(crate) async fn alter_async(
+        &self,
+        op: &api::Operation,
+        opt: CallOption,
+    ) -> grpcio::Result<api::Payload> {
+        let _in_flight = self.begin();
+        let result = match self.client.alter_async_opt(op, opt) {
+            Ok(receiver) => receiver.await,
+            Err(err) => Err(err),
+        };
+        self.observe(&result);
+        result
+    }
+
+    pub(crate) async fn commit_or_abort_async(
+        &self,
+        context: &api::TxnContext,
+        opt: CallOption,
+    ) -> grpcio::Result<api::TxnContext> {
+        let _in_flight = self.begin();
+        let result = match self.client.commit_or_abort_async_opt(context, opt) {
+            Ok(receiver) => receiver.await,
+            Err(err) => Err(err),
+        };
+        self.observe(&result);
+        result
+    }
+
+    pub(crate) async fn login_async(
+        &self,
+        request: &api::LoginRequest,
+        opt: CallOption,
+    ) -> grpcio::Result<api::Response> {
+        let _in_flight = self.begin();
+        let result = match self.client.login_async_opt(request, opt) {
+            Ok(receiver) => receiver.await,
+            Err(err) => Err(err),
+        };
+        self.observe(&result);
+        result
+    }
+}
+
+/// The set of endpoints behind a `Dgraph`, together with the strategy balancing between them.
+pub(crate) struct Pool {
+    endpoints: Vec<Endpoint>,
+    balancer: Box<dyn Balancer>,
+    health_checks_started: AtomicBool,
+}
+
+impl Pool {
+    pub(crate) fn new(
+        clients: Vec<api_grpc::DgraphClient>,
+        balancer: Box<dyn Balancer>,
+        ejection_time: Duration,
+    ) -> Pool {
+        let endpoints = clients
+            .into_iter()
+            .map(|client| Endpoint {
+                client,
+                in_flight: AtomicUsize::new(0),
+                ejected_until: Mutex::new(None),
+                ejection_time,
+            })
+            .collect();
+
+        Pool {
+            endpoints,
+            balancer,
+            health_checks_started: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns the index of the endpoint that should serve the next request.
+    pub(crate) fn pick(&self) -> Option<usize> {
+        let now = Instant::now();
+        let stats = |(index, endpoint): (usize, &Endpoint)| EndpointStats {
+            index,
+            in_flight: endpoint.in_flight.load(Ordering::SeqCst),
+        };
+
+        let mut candidates: Vec<EndpointStats> = self
+            .endpoints
+            .iter()
+            .enumerate()
+            .filter(|(_, endpoint)| !endpoint.is_ejected(now))
+            .map(stats)
+            .collect();
+
+        if candidates.is_empty() {
+            candidates = self.endpoints.iter().enumerate().map(stats).collect();
+        }
+
+        if candidates.is_empty() {
+            return None;
+        }
+
+        let position = self.balancer.pick(&candidates) % candidates.len();
+        Some(candidates[position].index)
+    }
+
+    pub(crate) fn endpoint(&self, index: usize) -> &Endpoint {
+        &self.endpoints[index]
+    }
+
+    /// Marks the health checks as started, returning `false` if they already were.
+    pub(crate) fn start_health_checks(&self) -> bool {
+        !self.health_checks_started.swap(true, Ordering::SeqCst)
+    }
+
+    /// Calls `CheckVersion` on every endpoint at once, bringing back the ones that answer
+    /// within `timeout` and ejecting the ones that do not.
+    pub(crate) fn probe(&self, timeout: Duration) {
+        let probes = self.endpoints.iter().map(|endpoint| async move {
+            let opt = CallOption::default().timeout(timeout);
+            let result = match endpoint
+                .client
+                .check_version_async_opt(&api::Check::new(), opt)
+            {
+                Ok(receiver) => receiver.await,
+                Err(err) => Err(err),
+            };
+
+            match result {
+                Ok(_) => endpoint.set_ejected_until(None),
+                Err(_) => endpoint.set_ejected_until(Some(Instant::now() + endpoint.ejection_time)),
+            }
+        });
+
+        futures::executor::block_on(futures::future::join_all(probes));
+    }
+}
@@ -0,0 +1,200 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use grpcio::{ChannelBuilder, ChannelCredentialsBuilder, CompressionAlgorithms, EnvBuilder};
+
+use crate::balancer::{Balancer, Pool, RoundRobin, DEFAULT_EJECTION_TIME};
+use crate::client::Dgraph;
+use crate::errors::DgraphError;
+use crate::protos::api_grpc;
+
+/// Builder for a `Dgraph` client connected to one or more alpha endpoints.
+///
+/// All channels share a single gRPC environment, so the number of completion queue
+/// threads applies to the client as a whole.
+///
+/// ```no_run
+/// # fn main() -> Result<(), dgraph::DgraphError> {
+/// let dgraph = dgraph::Dgraph::builder()
+///     .endpoint("localhost:9080")
+///     .endpoint("localhost:9081")
+///     .max_receive_message_len(64 * 1024 * 1024)
+///     .login("groot", "password")
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Default)]
+pub struct DgraphBuilder {
+    endpoints: Vec<String>,
+    root_ca: Option<Vec<u8>>,
+    client_cert: Option<(Vec<u8>, Vec<u8>)>,
+    credentials: Option<(String, String)>,
+    keepalive: Option<(Duration, Duration)>,
+    max_send_message_len: Option<i32>,
+    max_receive_message_len: Option<i32>,
+    compression: Option<CompressionAlgorithms>,
+    cq_count: Option<usize>,
+    timeout: Option<Duration>,
+    balancer: Option<Box<dyn Balancer>>,
+    ejection_time: Option<Duration>,
+    health_check_interval: Option<Duration>,
+}
+
+impl DgraphBuilder {
+    pub fn new() -> DgraphBuilder {
+        Default::default()
+    }
+
+    /// Adds an alpha address, such as `localhost:9080`.
+    pub fn endpoint(mut self, addr: impl Into<String>) -> Self {
+        self.endpoints.push(addr.into());
+        self
+    }
+
+    pub fn endpoints<I, S>(mut self, addrs: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.endpoints.extend(addrs.into_iter().map(Into::into));
+        self
+    }
+
+    /// Connects over TLS, verifying the server with the given PEM encoded root certificate.
+    pub fn root_ca(mut self, root_ca: Vec<u8>) -> Self {
+        self.root_ca = Some(root_ca);
+        self
+    }
+
+    /// Connects over TLS, presenting the given PEM encoded client certificate and key.
+    pub fn client_cert(mut self, cert: Vec<u8>, private_key: Vec<u8>) -> Self {
+        self.client_cert = Some((cert, private_key));
+        self
+    }
+
+    /// Logs in with the given credentials as part of `build`.
+    pub fn login(mut self, userid: impl Into<String>, password: impl Into<String>) -> Self {
+        self.credentials = Some((userid.into(), password.into()));
+        self
+    }
+
+    /// Sends keepalive pings every `time`, dropping the connection if one is not
+    /// acknowledged within `timeout`.
+    pub fn keepalive(mut self, time: Duration, timeout: Duration) -> Self {
+        self.keepalive = Some((time, timeout));
+        self
+    }
+
+    pub fn max_send_message_len(mut self, len: i32) -> Self {
+        self.max_send_message_len = Some(len);
+        self
+    }
+
+    pub fn max_receive_message_len(mut self, len: i32) -> Self {
+        self.max_receive_message_len = Some(len);
+        self
+    }
+
+    pub fn compression(mut self, algorithm: CompressionAlgorithms) -> Self {
+        self.compression = Some(algorithm);
+        self
+    }
+
+    /// Sets the number of completion queue threads polling the gRPC environment.
+    pub fn completion_queues(mut self, count: usize) -> Self {
+        self.cq_count = Some(count);
+        self
+    }
+
+    /// Sets the default deadline of every call, see `Dgraph::set_timeout`.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the strategy spreading requests across the endpoints. Defaults to `RoundRobin`.
+    pub fn balancer(mut self, balancer: impl Balancer + 'static) -> Self {
+        self.balancer = Some(Box::new(balancer));
+        self
+    }
+
+    /// Sets how long an endpoint stays out of rotation after failing with `UNAVAILABLE`.
+    /// Defaults to `DEFAULT_EJECTION_TIME`.
+    pub fn ejection_time(mut self, ejection_time: Duration) -> Self {
+        self.ejection_time = Some(ejection_time);
+        self
+    }
+
+    /// Starts active health checks once built, see `Dgraph::start_health_checks`.
+    pub fn health_check_interval(mut self, interval: Duration) -> Self {
+        self.health_check_interval = Some(interval);
+        self
+    }
+
+    /// Connects to the endpoints. Fails with `DgraphError::NoClients` if none were added.
+    pub fn build(self) -> Result<Dgraph, DgraphError> {
+        if self.endpoints.is_empty() {
+            return Err(DgraphError::NoClients);
+        }
+
+        let mut env = EnvBuilder::new();
+        if let Some(count) = self.cq_count {
+            env = env.cq_count(count);
+        }
+        let env = Arc::new(env.build());
+
+        let clients = self
+            .endpoints
+            .iter()
+            .map(|addr| {
+                let mut channel = ChannelBuilder::new(env.clone());
+                if let Some((time, timeout)) = self.keepalive {
+                    channel = channel.keepalive_time(time).keepalive_timeout(timeout);
+                }
+                if let Some(len) = self.max_send_message_len {
+                    channel = channel.max_send_message_len(len);
+                }
+                if let Some(len) = self.max_receive_message_len {
+                    channel = channel.max_receive_message_len(len);
+                }
+                if let Some(algorithm) = self.compression {
+                    channel = channel.default_compression_algorithm(algorithm);
+                }
+
+                let channel = if self.root_ca.is_some() || self.client_cert.is_some() {
+                    let mut credentials = ChannelCredentialsBuilder::new();
+                    if let Some(root_ca) = &self.root_ca {
+                        credentials = credentials.root_cert(root_ca.clone());
+                    }
+                    if let Some((cert, private_key)) = &self.client_cert {
+                        credentials = credentials.cert(cert.clone(), private_key.clone());
+                    }
+                    channel.secure_connect(addr, credentials.build())
+                } else {
+                    channel.connect(addr)
+                };
+
+                api_grpc::DgraphClient::new(channel)
+            })
+            .collect();
+
+        let balancer = self
+            .balancer
+            .unwrap_or_else(|| Box::new(RoundRobin::default()));
+        let ejection_time = self.ejection_time.unwrap_or(DEFAULT_EJECTION_TIME);
+
+        let mut dgraph = Dgraph::with_pool(Pool::new(clients, balancer, ejection_time));
+        dgraph.set_timeout(self.timeout);
+
+        if let Some(interval) = self.health_check_interval {
+            dgraph.start_health_checks(interval);
+        }
+
+        if let Some((userid, password)) = self.credentials {
+            dgraph.login(userid, password)?;
+        }
+
+        Ok(dgraph)
+    }
+}
@@ -1,15 +1,28 @@
-use rand::prelude::*;
-use std::sync::Mutex;
+use grpcio::{CallOption, MetadataBuilder};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
 
+use crate::balancer::{
+    Balancer, Endpoint, Pool, RoundRobin, DEFAULT_EJECTION_TIME, DEFAULT_PROBE_TIMEOUT,
+};
+use crate::builder::DgraphBuilder;
+use crate::dql;
 use crate::errors::DgraphError;
 use crate::protos::api;
 use crate::protos::api_grpc;
+use crate::retry::RetryPolicy;
+use crate::schema::Schema;
 use crate::txn::Txn;
 
 // Dgraph is a transaction aware client to a set of dgraph server instances.
+//
+// Cloning is cheap: clones share the connections, balancing state and login token.
+#[derive(Clone)]
 pub struct Dgraph {
-    jwt: Mutex<api::Jwt>,
-    dc: Vec<api_grpc::DgraphClient>,
+    jwt: Arc<Mutex<api::Jwt>>,
+    pool: Arc<Pool>,
+    timeout: Option<Duration>,
 }
 
 impl Dgraph {
@@ -19,15 +32,79 @@ impl Dgraph {
     /// cluster).
     ///
     /// A single client is thread safe for sharing with multiple go routines.
+    ///
+    /// Requests are spread across the clients in round-robin order.
     pub fn new(clients: Vec<api_grpc::DgraphClient>) -> Dgraph {
+        Dgraph::with_balancer(clients, RoundRobin::default())
+    }
+
+    /// Creates a new Dgraph that spreads requests across the clients with the given
+    /// balancing strategy.
+    pub fn with_balancer(
+        clients: Vec<api_grpc::DgraphClient>,
+        balancer: impl Balancer + 'static,
+    ) -> Dgraph {
+        Dgraph::with_pool(Pool::new(
+            clients,
+            Box::new(balancer),
+            DEFAULT_EJECTION_TIME,
+        ))
+    }
+
+    pub(crate) fn with_pool(pool: Pool) -> Dgraph {
         Dgraph {
-            jwt: Mutex::new(api::Jwt::new()),
-            dc: clients,
+            jwt: Arc::new(Mutex::new(api::Jwt::new())),
+            pool: Arc::new(pool),
+            timeout: None,
         }
     }
 
+    /// Probes every endpoint with `CheckVersion` once per `interval` on a background thread.
+    ///
+    /// Endpoints are passively ejected from the rotation when a call fails with
+    /// `UNAVAILABLE`; the probes eject unresponsive endpoints earlier and bring recovered
+    /// ones back before their ejection time runs out. All endpoints are probed at once, each
+    /// given `DEFAULT_PROBE_TIMEOUT` to answer, or `interval` if that is shorter.
+    ///
+    /// The thread is shared by all clones of this client, so calling this again does
+    /// nothing, and it stops once the last of them is dropped.
+    pub fn start_health_checks(&self, interval: Duration) {
+        if !self.pool.start_health_checks() {
+            return;
+        }
+
+        let pool = Arc::downgrade(&self.pool);
+        let timeout = interval.min(DEFAULT_PROBE_TIMEOUT);
+
+        thread::spawn(move || loop {
+            thread::sleep(interval);
+
+            match pool.upgrade() {
+                Some(pool) => pool.probe(timeout),
+                None => break,
+            }
+        });
+    }
+
+    /// Sets the default deadline of every call made by this client and the transactions it
+    /// creates afterwards. Calls exceeding it fail with `DgraphError::DeadlineExceeded`.
+    ///
+    /// The deadline belongs to this handle only; existing clones keep their own.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Returns a builder that configures the channels, credentials and defaults of a client.
+    pub fn builder() -> DgraphBuilder {
+        DgraphBuilder::new()
+    }
+
     pub fn login(&mut self, userid: String, password: String) -> Result<(), DgraphError> {
-        let dc = self.any_client().expect("Cannot login. No client present");
+        let endpoint = self.endpoint()?;
 
         let login_request = api::LoginRequest {
             userid,
@@ -35,20 +112,17 @@ impl Dgraph {
             ..Default::default()
         };
 
-        let res = dc.login(&login_request)?;
-        let jwt = protobuf::parse_from_bytes::<api::Jwt>(res.get_json()).unwrap();
+        let res = endpoint.login(&login_request, login_option(self.timeout))?;
+        let jwt = parse_jwt(&res)?;
 
-        *self
-            .jwt
-            .lock()
-            .expect("Unable to block or acquire lock to jwt mutex") = jwt;
+        *self.jwt()? = jwt;
 
         Ok(())
     }
 
     pub fn alter(&self, op: &api::Operation) -> Result<api::Payload, DgraphError> {
-        let dc = self.any_client().expect("Cannot alter. No client present");
-        let res = dc.alter(op);
+        let endpoint = self.endpoint()?;
+        let res = endpoint.alter(op, self.call_option(self.timeout)?);
 
         match res {
             Ok(res) => Ok(res),
@@ -56,40 +130,134 @@ impl Dgraph {
                 if self.is_jwt_expired(&err) {
                     self.retry_login()?;
 
-                    let res = dc.alter(op)?;
+                    let res = endpoint
+                        .alter(op, self.call_option(self.timeout)?)
+                        .map_err(|err| DgraphError::from(err).into_schema_error(op))?;
 
                     Ok(res)
                 } else {
-                    Err(err.into())
+                    Err(DgraphError::from(err).into_schema_error(op))
                 }
             }
         }
     }
 
+    /// Reads the schema the server currently has: its predicates and the names of its types
+    /// through `schema {}`, then the fields of those types through `schema(type: [...])`,
+    /// both at the same snapshot. Dgraph's internal `dgraph.*` predicates and types are left
+    /// out, so the result can be compared against a desired schema with `schema::diff`.
+    pub fn current_schema(&self) -> Result<Schema, DgraphError> {
+        let mut txn = self.new_readonly_txn()?;
+        let mut schema = Schema::from_response(&txn.query("schema {}")?)?;
+
+        if !schema.types.is_empty() {
+            let names = schema
+                .types
+                .iter()
+                .map(|t| dql::name(&t.name))
+                .collect::<Result<Vec<_>, _>>()?;
+            let query = format!("schema(type: [{}]) {{}}", names.join(", "));
+            schema.types = Schema::from_response(&txn.query(&query)?)?.types;
+        }
+
+        Ok(schema)
+    }
+
+    /// Picks one of the clients with the configured balancing strategy, skipping ejected
+    /// endpoints while healthy ones are left.
     pub fn any_client(&self) -> Option<&api_grpc::DgraphClient> {
-        let mut rng = thread_rng();
+        self.pool
+            .pick()
+            .map(|index| &self.pool.endpoint(index).client)
+    }
+
+    /// Picks one of the endpoints, failing with `DgraphError::NoClients` when there are none.
+    fn endpoint(&self) -> Result<&Endpoint, DgraphError> {
+        Ok(self.pool.endpoint(self.pick_endpoint()?))
+    }
+
+    fn pick_endpoint(&self) -> Result<usize, DgraphError> {
+        self.pool.pick().ok_or(DgraphError::NoClients)
+    }
+
+    pub(crate) fn endpoint_at(&self, index: usize) -> &Endpoint {
+        self.pool.endpoint(index)
+    }
 
-        self.dc.choose(&mut rng)
+    fn jwt(&self) -> Result<MutexGuard<'_, api::Jwt>, DgraphError> {
+        self.jwt.lock().map_err(|_| DgraphError::LockPoisoned)
     }
 
-    pub fn new_txn(&self) -> Txn {
-        Txn {
+    /// Creates a new transaction bound to one of the clients. This operation incurs no
+    /// network overhead, but fails with `DgraphError::NoClients` if there are no clients.
+    ///
+    /// The transaction owns a clone of this client, so it is `Send + 'static` and can be
+    /// moved into another thread or task.
+    pub fn new_txn(&self) -> Result<Txn, DgraphError> {
+        Ok(Txn {
             context: Default::default(),
             finished: false,
             mutated: false,
+            commit_sent: false,
             read_only: false,
             best_effort: false,
-            client: self
-                .any_client()
-                .expect("Cannot create transactions. No client present!"),
-            dgraph: self,
-        }
+            timeout: self.timeout,
+            endpoint_index: self.pick_endpoint()?,
+            dgraph: self.clone(),
+        })
     }
 
-    pub fn new_readonly_txn(&self) -> Txn {
-        let mut txn = self.new_txn();
+    pub fn new_readonly_txn(&self) -> Result<Txn, DgraphError> {
+        let mut txn = self.new_txn()?;
         txn.read_only = true;
-        txn
+        Ok(txn)
+    }
+
+    /// Runs `f` in a fresh transaction and commits it, retrying with the default
+    /// `RetryPolicy` when it fails with a retryable error. See `run_in_txn_with`.
+    pub fn run_in_txn<F, T>(&self, f: F) -> Result<T, DgraphError>
+    where
+        F: FnMut(&mut Txn) -> Result<T, DgraphError>,
+    {
+        self.run_in_txn_with(&RetryPolicy::default(), f)
+    }
+
+    /// Runs `f` in a fresh transaction and commits it, returning the closure's result.
+    ///
+    /// When either the closure or the commit fails with an error for which
+    /// `DgraphError::is_retryable` holds, such as an aborted transaction, the whole unit of
+    /// work is retried in a new transaction after a jittered backoff, up to
+    /// `policy.max_attempts` times. So is `DgraphError::Unavailable` from the closure, as
+    /// long as it did not send a `commit_now` mutation. Any other error is returned
+    /// straight away. The closure may also finish the transaction itself with `commit_now`.
+    pub fn run_in_txn_with<F, T>(&self, policy: &RetryPolicy, mut f: F) -> Result<T, DgraphError>
+    where
+        F: FnMut(&mut Txn) -> Result<T, DgraphError>,
+    {
+        let mut attempt = 0;
+
+        loop {
+            attempt += 1;
+
+            let mut txn = self.new_txn()?;
+            let (result, committing) = match f(&mut txn) {
+                Ok(value) if txn.finished => (Ok(value), true),
+                Ok(value) => (txn.commit().map(|_| value), true),
+                Err(err) => (Err(err), txn.commit_sent),
+            };
+
+            let retryable = |err: &DgraphError| match err {
+                DgraphError::Unavailable(_) => !committing,
+                err => err.is_retryable(),
+            };
+
+            match result {
+                Err(ref err) if retryable(err) && attempt < policy.max_attempts => {
+                    thread::sleep(policy.backoff(attempt));
+                }
+                result => return result,
+            }
+        }
     }
 
     pub fn is_jwt_expired(&self, grpc_error: &grpcio::Error) -> bool {
@@ -102,25 +270,141 @@ impl Dgraph {
         false
     }
 
+    /// Builds the options for an outgoing call, attaching the current access JWT as
+    /// `accessJwt` metadata once the client has logged in.
+    pub(crate) fn call_option(&self, timeout: Option<Duration>) -> Result<CallOption, DgraphError> {
+        let opt = login_option(timeout);
+        let jwt = self.jwt()?;
+
+        if jwt.access_jwt.is_empty() {
+            return Ok(opt);
+        }
+
+        let mut headers = MetadataBuilder::new();
+        headers.add_str("accessJwt", &jwt.access_jwt)?;
+
+        Ok(opt.headers(headers.build()))
+    }
+
     pub fn retry_login(&self) -> Result<(), DgraphError> {
-        let mut jwt = self
-            .jwt
-            .lock()
-            .expect("Unable to block or acquire lock to jwt mutex");
+        self.retry_login_with(self.timeout)
+    }
+
+    /// Refreshes the JWT within the deadline of the call that needed it.
+    pub(crate) fn retry_login_with(&self, timeout: Option<Duration>) -> Result<(), DgraphError> {
+        let mut jwt = self.jwt()?;
 
         if jwt.refresh_jwt.is_empty() {
             return Err(DgraphError::JwtRefreshTokenEmpty);
         }
 
-        let dc = self.any_client().expect("Cannot alter. No client present");
+        let endpoint = self.endpoint()?;
         let login_request = api::LoginRequest {
             refresh_token: jwt.refresh_jwt.clone(),
             ..Default::default()
         };
-        let response = dc.login(&login_request)?;
+        let response = endpoint.login(&login_request, login_option(timeout))?;
+
+        *jwt = parse_jwt(&response)?;
+
+        Ok(())
+    }
+
+    pub async fn login_async(
+        &mut self,
+        userid: String,
+        password: String,
+    ) -> Result<(), DgraphError> {
+        let endpoint = self.endpoint()?;
+
+        let login_request = api::LoginRequest {
+            userid,
+            password,
+            ..Default::default()
+        };
+
+        let res = endpoint
+            .login_async(&login_request, login_option(self.timeout))
+            .await?;
+        let jwt = parse_jwt(&res)?;
+
+        *self.jwt()? = jwt;
+
+        Ok(())
+    }
+
+    pub async fn alter_async(&self, op: &api::Operation) -> Result<api::Payload, DgraphError> {
+        let endpoint = self.endpoint()?;
+        let res = endpoint
+            .alter_async(op, self.call_option(self.timeout)?)
+            .await;
+
+        match res {
+            Ok(res) => Ok(res),
+            Err(err) => {
+                if self.is_jwt_expired(&err) {
+                    self.retry_login_async().await?;
+
+                    let res = endpoint
+                        .alter_async(op, self.call_option(self.timeout)?)
+                        .await
+                        .map_err(|err| DgraphError::from(err).into_schema_error(op))?;
+
+                    Ok(res)
+                } else {
+                    Err(DgraphError::from(err).into_schema_error(op))
+                }
+            }
+        }
+    }
+
+    /// Async version of `retry_login`. The jwt mutex is not held across the login call, so
+    /// concurrent refreshes may race; the last successful response wins.
+    pub async fn retry_login_async(&self) -> Result<(), DgraphError> {
+        self.retry_login_async_with(self.timeout).await
+    }
+
+    pub(crate) async fn retry_login_async_with(
+        &self,
+        timeout: Option<Duration>,
+    ) -> Result<(), DgraphError> {
+        let refresh_jwt = {
+            let jwt = self.jwt()?;
+
+            if jwt.refresh_jwt.is_empty() {
+                return Err(DgraphError::JwtRefreshTokenEmpty);
+            }
+
+            jwt.refresh_jwt.clone()
+        };
+
+        let endpoint = self.endpoint()?;
+        let login_request = api::LoginRequest {
+            refresh_token: refresh_jwt,
+            ..Default::default()
+        };
+        let response = endpoint
+            .login_async(&login_request, login_option(timeout))
+            .await?;
+        let jwt = parse_jwt(&response)?;
 
-        *jwt = serde_json::from_str(std::str::from_utf8(&response.json).unwrap()).unwrap();
+        *self.jwt()? = jwt;
 
         Ok(())
     }
 }
+
+/// Options for a login or JWT refresh, which carry the deadline but never the `accessJwt`
+/// header of the session being replaced.
+fn login_option(timeout: Option<Duration>) -> CallOption {
+    match timeout {
+        Some(timeout) => CallOption::default().timeout(timeout),
+        None => CallOption::default(),
+    }
+}
+
+/// Login responses carry the protobuf encoded `Jwt` in their `json` field.
+fn parse_jwt(response: &api::Response) -> Result<api::Jwt, DgraphError> {
+    protobuf::parse_from_bytes::<api::Jwt>(response.get_json())
+        .map_err(DgraphError::InvalidLoginResponse)
+}
@@ -0,0 +1,200 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Splits a point in time into whole seconds since the Unix epoch and the nanoseconds past
+/// that second, which are always positive.
+pub(crate) fn to_unix(time: SystemTime) -> (i64, u32) {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(since) => (since.as_secs() as i64, since.subsec_nanos()),
+        Err(err) => {
+            let before = err.duration();
+            let secs = -(before.as_secs() as i64);
+
+            match before.subsec_nanos() {
+                0 => (secs, 0),
+                nanos => (secs - 1, 1_000_000_000 - nanos),
+            }
+        }
+    }
+}
+
+/// Formats a point in time as RFC 3339 in UTC, the textual form Dgraph uses for `datetime`.
+pub(crate) fn to_rfc3339(time: SystemTime) -> String {
+    let (secs, nanos) = to_unix(time);
+    let (year, month, day) = civil_from_days(secs.div_euclid(86_400));
+    let seconds_of_day = secs.rem_euclid(86_400);
+
+    let mut text = format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day % 3600 / 60,
+        seconds_of_day % 60
+    );
+
+    if nanos > 0 {
+        let fraction = format!("{:09}", nanos);
+        text.push('.');
+        text.push_str(fraction.trim_end_matches('0'));
+    }
+
+    text.push('Z');
+    text
+}
+
+/// Converts days since 1970-01-01 into a proleptic Gregorian (year, month, day).
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = (if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    }) as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+/// Seconds from 0001-01-01 to the Unix epoch, the offset Go's `time` package counts from.
+const UNIX_TO_INTERNAL: i64 = 62_135_596_800;
+
+/// Encodes a point in time the way Go's `time.Time.MarshalBinary` does for UTC, which is how
+/// Dgraph expects `datetime` values and facets in binary form.
+pub(crate) fn to_binary(time: SystemTime) -> Vec<u8> {
+    let (secs, nanos) = to_unix(time);
+
+    let mut bytes = Vec::with_capacity(15);
+    bytes.push(1);
+    bytes.extend_from_slice(&(secs + UNIX_TO_INTERNAL).to_be_bytes());
+    bytes.extend_from_slice(&(nanos as i32).to_be_bytes());
+    // An offset of -1 minutes marks the time as UTC.
+    bytes.extend_from_slice(&(-1i16).to_be_bytes());
+    bytes
+}
+
+/// Decodes a time encoded by `to_binary`, or by Go with any zone offset.
+pub(crate) fn from_binary(bytes: &[u8]) -> Option<SystemTime> {
+    match bytes.first() {
+        Some(1) if bytes.len() == 15 => (),
+        Some(2) if bytes.len() == 16 => (),
+        _ => return None,
+    }
+
+    let mut secs = [0; 8];
+    secs.copy_from_slice(&bytes[1..9]);
+    let mut nanos = [0; 4];
+    nanos.copy_from_slice(&bytes[9..13]);
+
+    from_unix(
+        i64::from_be_bytes(secs) - UNIX_TO_INTERNAL,
+        i32::from_be_bytes(nanos) as u32,
+    )
+}
+
+/// Parses the date and time formats Dgraph accepts for `datetime`: `2006`, `2006-01`,
+/// `2006-01-02` and `2006-01-02T15:04:05` with optional minutes, seconds, fraction and zone
+/// (`Z` or `+07:00`). Times without a zone are taken as UTC.
+pub(crate) fn from_rfc3339(text: &str) -> Option<SystemTime> {
+    let (date, time) = match text.find(|c| c == 'T' || c == 't' || c == ' ') {
+        Some(split) => (&text[..split], Some(&text[split + 1..])),
+        None => (text, None),
+    };
+
+    let mut parts = date.split('-');
+    let year = number(parts.next()?, 4)?;
+    let month = parts.next().map_or(Some(1), |month| number(month, 2))?;
+    let day = parts.next().map_or(Some(1), |day| number(day, 2))?;
+    if parts.next().is_some() || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    let mut secs = days_from_civil(year, month as u32, day as u32) * 86_400;
+    let mut nanos = 0;
+
+    if let Some(time) = time {
+        let (clock, offset) = match time.find(|c| c == 'Z' || c == 'z' || c == '+' || c == '-') {
+            Some(split) => (&time[..split], &time[split..]),
+            None => (time, ""),
+        };
+
+        let (clock, fraction) = match clock.find('.') {
+            Some(dot) => (&clock[..dot], Some(&clock[dot + 1..])),
+            None => (clock, None),
+        };
+
+        let mut parts = clock.split(':');
+        let hour = number(parts.next()?, 2)?;
+        let minute = parts.next().map_or(Some(0), |minute| number(minute, 2))?;
+        let second = parts.next().map_or(Some(0), |second| number(second, 2))?;
+        if parts.next().is_some() || hour > 23 || minute > 59 || second > 60 {
+            return None;
+        }
+        secs += hour * 3600 + minute * 60 + second;
+
+        if let Some(fraction) = fraction {
+            if fraction.is_empty() || fraction.len() > 9 {
+                return None;
+            }
+            nanos = number(fraction, fraction.len())? as u32 * 10u32.pow(9 - fraction.len() as u32);
+        }
+
+        match offset {
+            "" | "Z" | "z" => (),
+            offset => {
+                let sign = if offset.starts_with('-') { -1 } else { 1 };
+                let mut parts = offset[1..].split(':');
+                let hours = number(parts.next()?, 2)?;
+                let minutes = parts.next().map_or(Some(0), |minutes| number(minutes, 2))?;
+                if parts.next().is_some() {
+                    return None;
+                }
+                secs -= sign * (hours * 3600 + minutes * 60);
+            }
+        }
+    }
+
+    from_unix(secs, nanos)
+}
+
+/// Parses exactly `digits` ASCII digits.
+fn number(text: &str, digits: usize) -> Option<i64> {
+    if text.len() != digits || !text.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    text.parse().ok()
+}
+
+/// The inverse of `to_unix`.
+pub(crate) fn from_unix(secs: i64, nanos: u32) -> Option<SystemTime> {
+    if nanos >= 1_000_000_000 {
+        return None;
+    }
+
+    if secs >= 0 {
+        UNIX_EPOCH.checked_add(Duration::new(secs as u64, nanos))
+    } else {
+        UNIX_EPOCH
+            .checked_sub(Duration::from_secs(-secs as u64))?
+            .checked_add(Duration::from_nanos(u64::from(nanos)))
+    }
+}
+
+/// Converts a proleptic Gregorian (year, month, day) into days since 1970-01-01.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month_index = i64::from(if month > 2 { month - 3 } else { month + 9 });
+    let day_of_year = (153 * month_index + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
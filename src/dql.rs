@@ -0,0 +1,737 @@
+//! A builder for DQL queries.
+//!
+//! Values passed to functions and pagination are never spliced into the query text: each one
+//! becomes a `$vN` variable declared in the query header and returned in the `Vars` that go
+//! along with it. Predicate, alias and variable names are checked when the query is built.
+//!
+//! ```no_run
+//! use dgraph::dql::{not, Block, Func, Query};
+//!
+//! # fn main() -> Result<(), dgraph::DgraphError> {
+//! # let dgraph = dgraph::make_dgraph!(dgraph::new_dgraph_client("localhost:9080"));
+//! let (query, vars) = Query::new()
+//!     .block(
+//!         Block::root("people", Func::anyofterms("name", "Alice Bob"))
+//!             .filter(Func::has("age").and(not(Func::eq("married", true))))
+//!             .order_asc("age")
+//!             .first(10)
+//!             .fields(&["uid", "name", "age"])
+//!             .edge(Block::edge("friend").first(3).field("name")),
+//!     )
+//!     .build()?;
+//!
+//! let response = dgraph.new_readonly_txn()?.query_with_vars(&query, vars)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::time::SystemTime;
+
+use crate::datetime;
+use crate::errors::DgraphError;
+use crate::uid::Uid;
+use crate::vars::Vars;
+
+/// A value passed to a function or used for pagination.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    String(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Uid(Uid),
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Value {
+        Value::String(value.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Value {
+        Value::String(value)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Value {
+        Value::Int(value)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(value: i32) -> Value {
+        Value::Int(value.into())
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Value {
+        Value::Float(value)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Value {
+        Value::Bool(value)
+    }
+}
+
+impl From<Uid> for Value {
+    fn from(value: Uid) -> Value {
+        Value::Uid(value)
+    }
+}
+
+/// Datetimes are compared in their RFC 3339 form.
+impl From<SystemTime> for Value {
+    fn from(value: SystemTime) -> Value {
+        Value::String(datetime::to_rfc3339(value))
+    }
+}
+
+/// A function used at the root of a block or inside a filter, built with the constructors
+/// below.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Func(FuncKind);
+
+#[derive(Clone, Debug, PartialEq)]
+enum FuncKind {
+    /// `uid(0x1, 0x2)`
+    Uid(Vec<Uid>),
+    /// `uid(v)`, the nodes held by a query variable.
+    UidVar(String),
+    /// `type(Person)`
+    Type(String),
+    /// `has(name)`
+    Has(String),
+    /// Comparisons such as `eq(name, "Alice")` or `ge(age, 18)`.
+    Compare(&'static str, String, Value),
+    /// Term and full-text search such as `anyofterms(name, "Alice Bob")`.
+    Text(&'static str, String, String),
+    /// `near(loc, [lon, lat], distance)`
+    Near(String, [f64; 2], f64),
+    /// `within`, `contains` and `intersects` with a polygon or point.
+    Geo(&'static str, String, Vec<[f64; 2]>),
+}
+
+impl Func {
+    pub fn uid(uids: impl IntoIterator<Item = impl Into<Uid>>) -> Func {
+        Func(FuncKind::Uid(uids.into_iter().map(Into::into).collect()))
+    }
+
+    pub fn uid_var(var: impl Into<String>) -> Func {
+        Func(FuncKind::UidVar(var.into()))
+    }
+
+    pub fn dgraph_type(name: impl Into<String>) -> Func {
+        Func(FuncKind::Type(name.into()))
+    }
+
+    pub fn has(predicate: impl Into<String>) -> Func {
+        Func(FuncKind::Has(predicate.into()))
+    }
+
+    pub fn eq(predicate: impl Into<String>, value: impl Into<Value>) -> Func {
+        Func(FuncKind::Compare("eq", predicate.into(), value.into()))
+    }
+
+    pub fn le(predicate: impl Into<String>, value: impl Into<Value>) -> Func {
+        Func(FuncKind::Compare("le", predicate.into(), value.into()))
+    }
+
+    pub fn lt(predicate: impl Into<String>, value: impl Into<Value>) -> Func {
+        Func(FuncKind::Compare("lt", predicate.into(), value.into()))
+    }
+
+    pub fn ge(predicate: impl Into<String>, value: impl Into<Value>) -> Func {
+        Func(FuncKind::Compare("ge", predicate.into(), value.into()))
+    }
+
+    pub fn gt(predicate: impl Into<String>, value: impl Into<Value>) -> Func {
+        Func(FuncKind::Compare("gt", predicate.into(), value.into()))
+    }
+
+    pub fn anyofterms(predicate: impl Into<String>, terms: impl Into<String>) -> Func {
+        Func(FuncKind::Text("anyofterms", predicate.into(), terms.into()))
+    }
+
+    pub fn allofterms(predicate: impl Into<String>, terms: impl Into<String>) -> Func {
+        Func(FuncKind::Text("allofterms", predicate.into(), terms.into()))
+    }
+
+    pub fn anyoftext(predicate: impl Into<String>, text: impl Into<String>) -> Func {
+        Func(FuncKind::Text("anyoftext", predicate.into(), text.into()))
+    }
+
+    pub fn alloftext(predicate: impl Into<String>, text: impl Into<String>) -> Func {
+        Func(FuncKind::Text("alloftext", predicate.into(), text.into()))
+    }
+
+    /// Nodes within `distance` metres of a `[longitude, latitude]` point.
+    pub fn near(predicate: impl Into<String>, point: [f64; 2], distance: f64) -> Func {
+        Func(FuncKind::Near(predicate.into(), point, distance))
+    }
+
+    /// Nodes whose location lies within the polygon.
+    pub fn within(predicate: impl Into<String>, polygon: Vec<[f64; 2]>) -> Func {
+        Func(FuncKind::Geo("within", predicate.into(), polygon))
+    }
+
+    /// Nodes whose polygon contains the `[longitude, latitude]` point.
+    pub fn contains(predicate: impl Into<String>, point: [f64; 2]) -> Func {
+        Func(FuncKind::Geo("contains", predicate.into(), vec![point]))
+    }
+
+    /// Nodes whose polygon intersects the given one.
+    pub fn intersects(predicate: impl Into<String>, polygon: Vec<[f64; 2]>) -> Func {
+        Func(FuncKind::Geo("intersects", predicate.into(), polygon))
+    }
+
+    pub fn and(self, other: impl Into<Filter>) -> Filter {
+        Filter::from(self).and(other)
+    }
+
+    pub fn or(self, other: impl Into<Filter>) -> Filter {
+        Filter::from(self).or(other)
+    }
+
+    fn render(&self, renderer: &mut Renderer) -> Result<String, DgraphError> {
+        Ok(match &self.0 {
+            FuncKind::Uid(uids) => {
+                let uids: Vec<String> = uids.iter().map(Uid::to_string).collect();
+                let list = if uids.len() == 1 {
+                    uids[0].clone()
+                } else {
+                    format!("[{}]", uids.join(", "))
+                };
+                format!("uid({})", renderer.param(&Value::String(list)))
+            }
+            FuncKind::UidVar(var) => format!("uid({})", name(var)?),
+            FuncKind::Type(type_name) => format!("type({})", name(type_name)?),
+            FuncKind::Has(predicate) => format!("has({})", predicate_name(predicate)?),
+            FuncKind::Compare(func, predicate, value) => format!(
+                "{}({}, {})",
+                func,
+                predicate_name(predicate)?,
+                renderer.param(value)
+            ),
+            FuncKind::Text(func, predicate, text) => format!(
+                "{}({}, {})",
+                func,
+                predicate_name(predicate)?,
+                renderer.param(&Value::String(text.clone()))
+            ),
+            FuncKind::Near(predicate, point, distance) => format!(
+                "near({}, {}, {})",
+                predicate_name(predicate)?,
+                coordinates(&[*point])?,
+                number(*distance)?
+            ),
+            FuncKind::Geo("contains", predicate, point) => format!(
+                "contains({}, {})",
+                predicate_name(predicate)?,
+                coordinates(point)?
+            ),
+            FuncKind::Geo(func, predicate, polygon) => format!(
+                "{}({}, [[{}]])",
+                func,
+                predicate_name(predicate)?,
+                coordinates(polygon)?
+            ),
+        })
+    }
+}
+
+/// A boolean combination of functions for `@filter`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Filter {
+    Func(Func),
+    And(Vec<Filter>),
+    Or(Vec<Filter>),
+    Not(Box<Filter>),
+}
+
+/// Negates a filter.
+pub fn not(filter: impl Into<Filter>) -> Filter {
+    Filter::Not(Box::new(filter.into()))
+}
+
+impl Filter {
+    pub fn and(self, other: impl Into<Filter>) -> Filter {
+        match self {
+            Filter::And(mut filters) => {
+                filters.push(other.into());
+                Filter::And(filters)
+            }
+            filter => Filter::And(vec![filter, other.into()]),
+        }
+    }
+
+    pub fn or(self, other: impl Into<Filter>) -> Filter {
+        match self {
+            Filter::Or(mut filters) => {
+                filters.push(other.into());
+                Filter::Or(filters)
+            }
+            filter => Filter::Or(vec![filter, other.into()]),
+        }
+    }
+
+    fn render(&self, renderer: &mut Renderer) -> Result<String, DgraphError> {
+        match self {
+            Filter::And(_) | Filter::Or(_) => Ok(format!("({})", self.render_top(renderer)?)),
+            _ => self.render_top(renderer),
+        }
+    }
+
+    /// Renders the filter without the parentheses a nested `AND` or `OR` needs.
+    fn render_top(&self, renderer: &mut Renderer) -> Result<String, DgraphError> {
+        match self {
+            Filter::Func(func) => func.render(renderer),
+            Filter::And(filters) => join(filters, " AND ", renderer),
+            Filter::Or(filters) => join(filters, " OR ", renderer),
+            Filter::Not(filter) => Ok(format!("NOT {}", filter.render(renderer)?)),
+        }
+    }
+}
+
+fn join(filters: &[Filter], op: &str, renderer: &mut Renderer) -> Result<String, DgraphError> {
+    if filters.is_empty() {
+        return Err(DgraphError::InvalidDql(format!(
+            "filter `{}` has nothing to combine",
+            op.trim()
+        )));
+    }
+
+    let parts = filters
+        .iter()
+        .map(|filter| filter.render(renderer))
+        .collect::<Result<Vec<_>, DgraphError>>()?;
+
+    Ok(parts.join(op))
+}
+
+impl From<Func> for Filter {
+    fn from(func: Func) -> Filter {
+        Filter::Func(func)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Field {
+    Predicate {
+        predicate: String,
+        alias: Option<String>,
+        var: Option<String>,
+    },
+    Edge(Block),
+    ExpandAll(Vec<String>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Order {
+    Asc(String),
+    Desc(String),
+}
+
+/// A query block, a `var` block or a nested edge.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Block {
+    name: String,
+    alias: Option<String>,
+    var: Option<String>,
+    func: Option<Func>,
+    filter: Option<Filter>,
+    first: Option<i64>,
+    offset: Option<i64>,
+    after: Option<Uid>,
+    order: Vec<Order>,
+    facets: Option<Vec<String>>,
+    recurse: Option<u32>,
+    cascade: bool,
+    normalize: bool,
+    fields: Vec<Field>,
+}
+
+impl Block {
+    fn new(name: String, func: Option<Func>) -> Block {
+        Block {
+            name,
+            alias: None,
+            var: None,
+            func,
+            filter: None,
+            first: None,
+            offset: None,
+            after: None,
+            order: Vec::new(),
+            facets: None,
+            recurse: None,
+            cascade: false,
+            normalize: false,
+            fields: Vec::new(),
+        }
+    }
+
+    /// A named query block, whose results are returned under `name`.
+    pub fn root(name: impl Into<String>, func: Func) -> Block {
+        Block::new(name.into(), Some(func))
+    }
+
+    /// A `var` block, whose results are only used through query variables.
+    pub fn var(func: Func) -> Block {
+        Block::new("var".to_string(), Some(func))
+    }
+
+    /// A nested block following the edge `predicate`.
+    pub fn edge(predicate: impl Into<String>) -> Block {
+        Block::new(predicate.into(), None)
+    }
+
+    /// Returns the edge under `alias` instead of its predicate name. Root and `var` blocks
+    /// are named by `Block::root` instead, and fail to build with an alias.
+    pub fn alias(mut self, alias: impl Into<String>) -> Self {
+        self.alias = Some(alias.into());
+        self
+    }
+
+    /// Stores the matched nodes in the query variable `var`, as in `v as var(func: ...)`.
+    pub fn as_var(mut self, var: impl Into<String>) -> Self {
+        self.var = Some(var.into());
+        self
+    }
+
+    pub fn filter(mut self, filter: impl Into<Filter>) -> Self {
+        self.filter = Some(filter.into());
+        self
+    }
+
+    pub fn first(mut self, first: i64) -> Self {
+        self.first = Some(first);
+        self
+    }
+
+    pub fn offset(mut self, offset: i64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    pub fn after(mut self, uid: impl Into<Uid>) -> Self {
+        self.after = Some(uid.into());
+        self
+    }
+
+    pub fn order_asc(mut self, predicate: impl Into<String>) -> Self {
+        self.order.push(Order::Asc(predicate.into()));
+        self
+    }
+
+    pub fn order_desc(mut self, predicate: impl Into<String>) -> Self {
+        self.order.push(Order::Desc(predicate.into()));
+        self
+    }
+
+    /// Requests all facets of the edge, or only the named ones when `facet` is called too.
+    pub fn facets(mut self) -> Self {
+        self.facets.get_or_insert_with(Vec::new);
+        self
+    }
+
+    pub fn facet(mut self, facet: impl Into<String>) -> Self {
+        self.facets.get_or_insert_with(Vec::new).push(facet.into());
+        self
+    }
+
+    /// Follows the block's edges recursively, up to `depth` levels.
+    pub fn recurse(mut self, depth: u32) -> Self {
+        self.recurse = Some(depth);
+        self
+    }
+
+    pub fn cascade(mut self) -> Self {
+        self.cascade = true;
+        self
+    }
+
+    pub fn normalize(mut self) -> Self {
+        self.normalize = true;
+        self
+    }
+
+    pub fn field(mut self, predicate: impl Into<String>) -> Self {
+        self.fields.push(Field::Predicate {
+            predicate: predicate.into(),
+            alias: None,
+            var: None,
+        });
+        self
+    }
+
+    pub fn fields(self, predicates: &[&str]) -> Self {
+        predicates
+            .iter()
+            .fold(self, |block, predicate| block.field(*predicate))
+    }
+
+    /// Returns `predicate` under `alias`, as in `alias: predicate`.
+    pub fn field_as(mut self, alias: impl Into<String>, predicate: impl Into<String>) -> Self {
+        self.fields.push(Field::Predicate {
+            predicate: predicate.into(),
+            alias: Some(alias.into()),
+            var: None,
+        });
+        self
+    }
+
+    /// Stores the values of `predicate` in the value variable `var`, as in `a as age`.
+    pub fn var_field(mut self, var: impl Into<String>, predicate: impl Into<String>) -> Self {
+        self.fields.push(Field::Predicate {
+            predicate: predicate.into(),
+            alias: None,
+            var: Some(var.into()),
+        });
+        self
+    }
+
+    pub fn edge(mut self, edge: Block) -> Self {
+        self.fields.push(Field::Edge(edge));
+        self
+    }
+
+    /// Returns every predicate of the node's `dgraph.type`, selecting `fields` of edge
+    /// targets, as in `expand(_all_) { uid }` for `&["uid"]`.
+    pub fn expand_all(mut self, fields: &[&str]) -> Self {
+        let fields = fields.iter().map(|field| field.to_string()).collect();
+        self.fields.push(Field::ExpandAll(fields));
+        self
+    }
+
+    fn render(&self, renderer: &mut Renderer, out: &mut String) -> Result<(), DgraphError> {
+        if let Some(var) = &self.var {
+            out.push_str(&format!("{} as ", name(var)?));
+        }
+
+        match &self.func {
+            Some(_) if self.alias.is_some() => {
+                return Err(DgraphError::InvalidDql(format!(
+                    "root block `{}` cannot have an alias",
+                    self.name
+                )))
+            }
+            Some(_) => out.push_str(&name(&self.name)?),
+            None => {
+                if let Some(alias) = &self.alias {
+                    out.push_str(&format!("{}: ", name(alias)?));
+                }
+                out.push_str(&predicate_name(&self.name)?);
+            }
+        }
+
+        let mut args = Vec::new();
+        if let Some(func) = &self.func {
+            args.push(format!("func: {}", func.render(renderer)?));
+        }
+        if let Some(first) = self.first {
+            args.push(format!("first: {}", renderer.param(&Value::Int(first))));
+        }
+        if let Some(offset) = self.offset {
+            args.push(format!("offset: {}", renderer.param(&Value::Int(offset))));
+        }
+        if let Some(after) = self.after {
+            args.push(format!("after: {}", renderer.param(&Value::Uid(after))));
+        }
+        for order in &self.order {
+            args.push(match order {
+                Order::Asc(predicate) => format!("orderasc: {}", predicate_name(predicate)?),
+                Order::Desc(predicate) => format!("orderdesc: {}", predicate_name(predicate)?),
+            });
+        }
+        if !args.is_empty() {
+            out.push_str(&format!("({})", args.join(", ")));
+        }
+
+        if let Some(filter) = &self.filter {
+            out.push_str(&format!(" @filter({})", filter.render_top(renderer)?));
+        }
+        if let Some(facets) = &self.facets {
+            let facets = facets
+                .iter()
+                .map(|facet| name(facet))
+                .collect::<Result<Vec<_>, _>>()?;
+            if facets.is_empty() {
+                out.push_str(" @facets");
+            } else {
+                out.push_str(&format!(" @facets({})", facets.join(", ")));
+            }
+        }
+        if let Some(depth) = self.recurse {
+            out.push_str(&format!(" @recurse(depth: {})", depth));
+        }
+        if self.cascade {
+            out.push_str(" @cascade");
+        }
+        if self.normalize {
+            out.push_str(" @normalize");
+        }
+
+        if !self.fields.is_empty() {
+            out.push_str(" {");
+            for field in &self.fields {
+                out.push(' ');
+                match field {
+                    Field::Predicate {
+                        predicate,
+                        alias,
+                        var,
+                    } => {
+                        if let Some(var) = var {
+                            out.push_str(&format!("{} as ", name(var)?));
+                        }
+                        if let Some(alias) = alias {
+                            out.push_str(&format!("{}: ", name(alias)?));
+                        }
+                        out.push_str(&predicate_name(predicate)?);
+                    }
+                    Field::Edge(edge) => edge.render(renderer, out)?,
+                    Field::ExpandAll(fields) => {
+                        out.push_str("expand(_all_)");
+                        if !fields.is_empty() {
+                            let fields = fields
+                                .iter()
+                                .map(|field| predicate_name(field))
+                                .collect::<Result<Vec<_>, _>>()?;
+                            out.push_str(&format!(" {{ {} }}", fields.join(" ")));
+                        }
+                    }
+                }
+            }
+            out.push_str(" }");
+        }
+
+        Ok(())
+    }
+}
+
+/// A whole query: any number of query and `var` blocks.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Query {
+    blocks: Vec<Block>,
+}
+
+impl Query {
+    pub fn new() -> Query {
+        Default::default()
+    }
+
+    pub fn block(mut self, block: Block) -> Self {
+        self.blocks.push(block);
+        self
+    }
+
+    /// Renders the query text along with the variables it declares.
+    pub fn build(&self) -> Result<(String, Vars), DgraphError> {
+        let mut renderer = Renderer::default();
+        let mut body = String::new();
+
+        for block in &self.blocks {
+            body.push_str("\n  ");
+            block.render(&mut renderer, &mut body)?;
+        }
+
+        let header = if renderer.declarations.is_empty() {
+            String::new()
+        } else {
+            format!("query q({}) ", renderer.declarations.join(", "))
+        };
+
+        Ok((format!("{}{{{}\n}}", header, body), renderer.vars))
+    }
+}
+
+#[derive(Default)]
+struct Renderer {
+    vars: Vars,
+    declarations: Vec<String>,
+}
+
+impl Renderer {
+    /// Declares a new variable holding `value` and returns its name.
+    fn param(&mut self, value: &Value) -> String {
+        let var = format!("$v{}", self.declarations.len());
+        let vars = std::mem::replace(&mut self.vars, Vars::new());
+
+        let (kind, vars) = match value {
+            Value::String(value) => ("string", vars.string(&var, value.clone())),
+            Value::Int(value) => ("int", vars.int(&var, *value)),
+            Value::Float(value) => ("float", vars.float(&var, *value)),
+            Value::Bool(value) => ("bool", vars.bool(&var, *value)),
+            Value::Uid(value) => ("string", vars.uid(&var, *value)),
+        };
+
+        self.vars = vars;
+        self.declarations.push(format!("{}: {}", var, kind));
+        var
+    }
+}
+
+fn invalid(what: &str, value: &str) -> DgraphError {
+    DgraphError::InvalidDql(format!("invalid {} `{}`", what, value))
+}
+
+/// Block, alias, variable, type and facet names.
+pub(crate) fn name(name: &str) -> Result<String, DgraphError> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '.');
+
+    if valid {
+        Ok(name.to_string())
+    } else {
+        Err(invalid("name", name))
+    }
+}
+
+/// Predicate names, which may also be reverse edges (`~friend`), carry a language tag
+/// (`name@en`) or be `uid`.
+fn predicate_name(predicate: &str) -> Result<String, DgraphError> {
+    let (base, lang) = match predicate.find('@') {
+        Some(at) => (&predicate[..at], Some(&predicate[at + 1..])),
+        None => (predicate, None),
+    };
+    let base = base.trim_start_matches('~');
+
+    let valid_lang = match lang {
+        Some(lang) => {
+            !lang.is_empty()
+                && lang
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || c == ':' || c == '.' || c == '-')
+        }
+        None => true,
+    };
+
+    match name(base) {
+        Ok(_) if valid_lang => Ok(predicate.to_string()),
+        _ => Err(invalid("predicate", predicate)),
+    }
+}
+
+fn number(value: f64) -> Result<String, DgraphError> {
+    if value.is_finite() {
+        Ok(value.to_string())
+    } else {
+        Err(invalid("coordinate", &value.to_string()))
+    }
+}
+
+fn coordinates(points: &[[f64; 2]]) -> Result<String, DgraphError> {
+    let points = points
+        .iter()
+        .map(|[lon, lat]| Ok(format!("[{}, {}]", number(*lon)?, number(*lat)?)))
+        .collect::<Result<Vec<String>, DgraphError>>()?;
+
+    Ok(points.join(", "))
+}
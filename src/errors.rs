@@ -1,9 +1,12 @@
 use std::error::Error;
 use std::fmt;
 
+use crate::protos::api;
+
 /// The error type for Dgraph operations.
 ///
-/// Native gRPC errors are wrapped into `GrpcError`.
+/// Failed gRPC calls are classified by their status code into the dedicated variants
+/// below. Anything that does not fit one of them is wrapped into `GrpcError`.
 #[derive(Debug)]
 pub enum DgraphError {
     TxnReadOnly,
@@ -13,13 +16,105 @@ pub enum DgraphError {
     WriteTxnBestEffort,
     StartTsMismatch,
     JwtRefreshTokenEmpty,
+    /// The client was created without any `DgraphClient` to send requests to.
+    NoClients,
+    /// The login response did not contain a valid JWT.
+    InvalidLoginResponse(protobuf::ProtobufError),
+    /// A thread panicked while holding the lock on the client's JWT.
+    LockPoisoned,
+    /// The transaction conflicted with a concurrent one and was aborted by the server.
+    TxnAborted,
+    Unauthenticated(String),
+    PermissionDenied(String),
+    Unavailable(String),
+    DeadlineExceeded,
+    /// The server rejected a query it could not parse.
+    QuerySyntax {
+        message: String,
+        line: usize,
+        column: usize,
+    },
+    /// The server rejected the schema of an `alter` operation.
+    SchemaError(String),
+    /// Schema text given to `schema::Schema::from_str` could not be parsed.
+    SchemaSyntax {
+        message: String,
+        line: usize,
+        column: usize,
+    },
+    /// RDF text given to `rdf::parse` could not be parsed.
+    RdfSyntax {
+        message: String,
+        line: usize,
+        column: usize,
+    },
+    /// A JSON response did not match the requested type. `json` holds the raw JSON around
+    /// the offending position.
+    Deserialize {
+        error: serde_json::Error,
+        json: String,
+    },
+    /// A value could not be serialized into mutation JSON.
+    Serialize(serde_json::Error),
+    /// The server returned a UID that is neither hex nor decimal.
+    InvalidUid(String),
+    /// Query variables do not match the variables declared in the query header.
+    InvalidVars(String),
+    /// A `dql` query builder was given a name or value it cannot render safely.
+    InvalidDql(String),
+    /// A loader worker thread panicked, for example in an `on_progress` callback.
+    LoaderPanicked,
+    /// Another process holds the migration lock. Holds the lock owner.
+    MigrationLocked(String),
+    /// The migrations are inconsistent with each other or with the ones already applied.
+    Migration(String),
+    Io(std::io::Error),
     GrpcError(grpcio::Error),
 }
 
+impl DgraphError {
+    /// Whether running the same unit of work again in a new transaction may succeed.
+    ///
+    /// `DeadlineExceeded` and `Unavailable` are deliberately not retryable, since the
+    /// server may have applied the request before it failed: retrying a `commit` or a
+    /// `commit_now` mutation could apply it twice. `Dgraph::run_in_txn` still retries
+    /// `Unavailable` from queries and mutations made before the commit, which are discarded
+    /// along with their transaction.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            DgraphError::TxnAborted => true,
+            _ => false,
+        }
+    }
+
+    /// Reclassifies an error returned by an `alter` of `op`, where a rejected request means
+    /// the schema itself is invalid. Alters that drop data without a schema are left as is.
+    pub(crate) fn into_schema_error(self, op: &api::Operation) -> DgraphError {
+        if op.schema.is_empty() {
+            return self;
+        }
+
+        match self {
+            DgraphError::QuerySyntax { message, .. } => DgraphError::SchemaError(message),
+            DgraphError::GrpcError(grpcio::Error::RpcFailure(status))
+                if status.status == grpcio::RpcStatusCode::UNKNOWN
+                    || status.status == grpcio::RpcStatusCode::INVALID_ARGUMENT =>
+            {
+                DgraphError::SchemaError(status.details.unwrap_or_default())
+            }
+            err => err,
+        }
+    }
+}
+
 impl Error for DgraphError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
             DgraphError::GrpcError(grpc_error) => Some(grpc_error),
+            DgraphError::InvalidLoginResponse(parse_error) => Some(parse_error),
+            DgraphError::Deserialize { error, .. } => Some(error),
+            DgraphError::Serialize(error) => Some(error),
+            DgraphError::Io(error) => Some(error),
             _ => None,
         }
     }
@@ -42,13 +137,125 @@ impl fmt::Display for DgraphError {
             DgraphError::MissingTxnContext => write!(f, "Missing Txn context on mutation response"),
             DgraphError::StartTsMismatch => write!(f, "StartTs mismatch"),
             DgraphError::JwtRefreshTokenEmpty => write!(f, "JWT refresh token is empty"),
+            DgraphError::NoClients => write!(f, "No client present"),
+            DgraphError::InvalidLoginResponse(ref parse_error) => {
+                write!(f, "Invalid login response: {}", parse_error)
+            }
+            DgraphError::LockPoisoned => write!(f, "Unable to acquire lock to jwt mutex"),
+            DgraphError::LoaderPanicked => write!(f, "A loader worker thread panicked"),
+            DgraphError::TxnAborted => write!(f, "Transaction has been aborted. Please retry"),
+            DgraphError::Unauthenticated(ref message) => {
+                write!(f, "Unauthenticated: {}", message)
+            }
+            DgraphError::PermissionDenied(ref message) => {
+                write!(f, "Permission denied: {}", message)
+            }
+            DgraphError::Unavailable(ref message) => write!(f, "Unavailable: {}", message),
+            DgraphError::DeadlineExceeded => {
+                write!(f, "Deadline exceeded before the call completed")
+            }
+            DgraphError::QuerySyntax {
+                ref message,
+                line,
+                column,
+            } => write!(
+                f,
+                "Query syntax error at line {} column {}: {}",
+                line, column, message
+            ),
+            DgraphError::SchemaError(ref message) => write!(f, "Schema error: {}", message),
+            DgraphError::SchemaSyntax {
+                ref message,
+                line,
+                column,
+            } => write!(
+                f,
+                "Schema syntax error at line {} column {}: {}",
+                line, column, message
+            ),
+            DgraphError::RdfSyntax {
+                ref message,
+                line,
+                column,
+            } => write!(
+                f,
+                "RDF syntax error at line {} column {}: {}",
+                line, column, message
+            ),
+            DgraphError::Deserialize {
+                ref error,
+                ref json,
+            } => write!(f, "Failed to deserialize response: {} in `{}`", error, json),
+            DgraphError::Serialize(ref error) => {
+                write!(f, "Failed to serialize mutation: {}", error)
+            }
+            DgraphError::InvalidUid(ref uid) => write!(f, "Invalid uid: {}", uid),
+            DgraphError::InvalidVars(ref message) => write!(f, "Invalid vars: {}", message),
+            DgraphError::InvalidDql(ref message) => write!(f, "Invalid dql: {}", message),
+            DgraphError::MigrationLocked(ref owner) => {
+                write!(f, "Migrations are locked by {}", owner)
+            }
+            DgraphError::Migration(ref message) => write!(f, "Migration error: {}", message),
+            DgraphError::Io(ref error) => write!(f, "I/O error: {}", error),
             DgraphError::GrpcError(ref grpc_error) => write!(f, "Grpc error: {}", grpc_error),
         }
     }
 }
 
+impl From<std::io::Error> for DgraphError {
+    fn from(err: std::io::Error) -> Self {
+        DgraphError::Io(err)
+    }
+}
+
 impl From<grpcio::Error> for DgraphError {
     fn from(err: grpcio::Error) -> Self {
-        DgraphError::GrpcError(err)
+        let status = match err {
+            grpcio::Error::RpcFailure(ref status) => status,
+            err => return DgraphError::GrpcError(err),
+        };
+        let message = status.details.clone().unwrap_or_default();
+
+        if status.status == grpcio::RpcStatusCode::ABORTED {
+            DgraphError::TxnAborted
+        } else if status.status == grpcio::RpcStatusCode::UNAUTHENTICATED {
+            DgraphError::Unauthenticated(message)
+        } else if status.status == grpcio::RpcStatusCode::PERMISSION_DENIED {
+            DgraphError::PermissionDenied(message)
+        } else if status.status == grpcio::RpcStatusCode::UNAVAILABLE {
+            DgraphError::Unavailable(message)
+        } else if status.status == grpcio::RpcStatusCode::DEADLINE_EXCEEDED {
+            DgraphError::DeadlineExceeded
+        } else if let Some((line, column)) = parse_position(status) {
+            DgraphError::QuerySyntax {
+                message,
+                line,
+                column,
+            }
+        } else {
+            DgraphError::GrpcError(err)
+        }
+    }
+}
+
+/// Finds the `line <n> column <m>` position Dgraph's lexer attaches to parse errors, which
+/// are rejected with an `INVALID_ARGUMENT` or `UNKNOWN` status.
+fn parse_position(status: &grpcio::RpcStatus) -> Option<(usize, usize)> {
+    if status.status != grpcio::RpcStatusCode::INVALID_ARGUMENT
+        && status.status != grpcio::RpcStatusCode::UNKNOWN
+    {
+        return None;
+    }
+
+    let message = status.details.as_deref()?;
+    let start = message.find("line ")?;
+    let mut words = message[start..].split_whitespace();
+
+    match (words.next(), words.next(), words.next(), words.next()) {
+        (Some("line"), Some(line), Some("column"), Some(column)) => {
+            let column = column.trim_end_matches(|c: char| !c.is_ascii_digit());
+            Some((line.parse().ok()?, column.parse().ok()?))
+        }
+        _ => None,
     }
 }
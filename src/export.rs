@@ -0,0 +1,283 @@
+//! Streaming export of query results to NDJSON or RDF.
+//!
+//! An `Exporter` pages through a root query block with `first`/`after` cursors, all in one
+//! read-only, best-effort transaction so every page sees the same snapshot, and writes each
+//! page before fetching the next, so memory use does not grow with the export.
+//!
+//! ```no_run
+//! use dgraph::export::Exporter;
+//! use dgraph::loader::Format;
+//!
+//! # fn main() -> Result<(), dgraph::DgraphError> {
+//! # let dgraph = dgraph::make_dgraph!(dgraph::new_dgraph_client("localhost:9080"));
+//! let nodes = Exporter::of_type("Person")
+//!     .format(Format::Rdf)
+//!     .gzip(true)
+//!     .export_file(&dgraph, "people.rdf.gz")?;
+//! # Ok(())
+//! # }
+//! ```
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::client::Dgraph;
+use crate::datetime;
+use crate::dql::{Block, Func, Query};
+use crate::errors::DgraphError;
+use crate::geo;
+use crate::loader::Format;
+use crate::nquad::FacetValue;
+use crate::protos::api;
+use crate::protos::api::Value_oneof_val::{default_val, geo_val};
+use crate::rdf;
+use crate::uid::Uid;
+
+pub const DEFAULT_PAGE_SIZE: i64 = 1000;
+
+/// Exports the nodes matched by a root query block.
+#[derive(Clone, Debug)]
+pub struct Exporter {
+    block: Block,
+    page_size: i64,
+    format: Format,
+    gzip: bool,
+}
+
+impl Exporter {
+    /// Exports the nodes returned by `block`, which must select `uid` and must not be
+    /// ordered, since pages follow each other by UID. Its `first` and `after` arguments are
+    /// replaced for every page.
+    pub fn new(block: Block) -> Exporter {
+        Exporter {
+            block,
+            page_size: DEFAULT_PAGE_SIZE,
+            format: Format::Json,
+            gzip: false,
+        }
+    }
+
+    /// Exports every node of a `dgraph.type` with all the predicates of its type.
+    pub fn of_type(type_name: impl Into<String>) -> Exporter {
+        Exporter::new(
+            Block::root("export", Func::dgraph_type(type_name))
+                .field("uid")
+                .field("dgraph.type")
+                .expand_all(&["uid"]),
+        )
+    }
+
+    pub fn page_size(mut self, page_size: i64) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// NDJSON, one node per line, by default; or N-Quads.
+    pub fn format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn gzip(mut self, gzip: bool) -> Self {
+        self.gzip = gzip;
+        self
+    }
+
+    /// Exports to a new file at `path`, returning the number of nodes written.
+    pub fn export_file(&self, dgraph: &Dgraph, path: impl AsRef<Path>) -> Result<u64, DgraphError> {
+        self.export_to(dgraph, File::create(path)?)
+    }
+
+    /// Exports to `out`, returning the number of nodes written.
+    pub fn export_to(&self, dgraph: &Dgraph, out: impl Write) -> Result<u64, DgraphError> {
+        if self.gzip {
+            let mut gzip = GzEncoder::new(BufWriter::new(out), Compression::default());
+            let nodes = self.write_pages(dgraph, &mut gzip)?;
+            gzip.finish()?.flush()?;
+            Ok(nodes)
+        } else {
+            let mut out = BufWriter::new(out);
+            let nodes = self.write_pages(dgraph, &mut out)?;
+            out.flush()?;
+            Ok(nodes)
+        }
+    }
+
+    fn write_pages(&self, dgraph: &Dgraph, out: &mut dyn Write) -> Result<u64, DgraphError> {
+        let mut txn = dgraph.new_readonly_txn()?;
+        txn.best_effort()?;
+
+        let mut nodes = 0;
+        let mut after = Uid(0);
+
+        loop {
+            let block = self.block.clone().first(self.page_size).after(after);
+            let (query, vars) = Query::new().block(block).build()?;
+            let blocks: HashMap<String, Vec<Value>> =
+                txn.query_with_vars(&query, vars)?.json_as()?;
+            let page = blocks
+                .into_iter()
+                .next()
+                .map_or_else(Vec::new, |(_, page)| page);
+
+            for node in &page {
+                self.write_node(node, out)?;
+            }
+            nodes += page.len() as u64;
+
+            match page.last() {
+                Some(last) if page.len() as i64 >= self.page_size => after = node_uid(last)?,
+                _ => return Ok(nodes),
+            }
+        }
+    }
+
+    fn write_node(&self, node: &Value, out: &mut dyn Write) -> Result<(), DgraphError> {
+        match self.format {
+            Format::Json => {
+                serde_json::to_writer(&mut *out, node).map_err(DgraphError::Serialize)?;
+                out.write_all(b"\n")?;
+            }
+            Format::Rdf => {
+                let subject = node_uid(node)?.to_string();
+                out.write_all(rdf::to_string(&node_nquads(&subject, node)).as_bytes())?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn node_uid(node: &Value) -> Result<Uid, DgraphError> {
+    match node.get("uid").and_then(Value::as_str) {
+        Some(uid) => uid.parse(),
+        None => Err(DgraphError::InvalidDql(
+            "exported nodes must select `uid`".to_string(),
+        )),
+    }
+}
+
+/// The N-Quads of a node's predicates, skipping reverse edges, aggregates and anything else
+/// that is not a stored predicate.
+fn node_nquads(subject: &str, node: &Value) -> Vec<api::NQuad> {
+    let object = match node.as_object() {
+        Some(object) => object,
+        None => return Vec::new(),
+    };
+
+    let mut nquads = Vec::new();
+    for (key, value) in object {
+        if key == "uid" || key.contains(|c| c == '|' || c == '~' || c == '(') {
+            continue;
+        }
+
+        let (predicate, lang) = match key.find('@') {
+            Some(at) => (&key[..at], &key[at + 1..]),
+            None => (key.as_str(), ""),
+        };
+        let value_facets = facets(object, key);
+
+        let values = match value {
+            Value::Array(values) => values.iter().collect(),
+            value => vec![value],
+        };
+
+        for (index, value) in values.into_iter().enumerate() {
+            let mut nquad = match value {
+                Value::Object(child) => {
+                    if let Some(uid) = child.get("uid").and_then(Value::as_str) {
+                        let mut nquad = api::NQuad::edge(subject, predicate, uid);
+                        for (facet, value) in facets(child, predicate) {
+                            nquad.facets.extend(facet_value(facet, value));
+                        }
+                        nquads.push(nquad);
+                        continue;
+                    }
+
+                    match geo::from_geojson(value) {
+                        Some(wkb) => {
+                            api::NQuad::literal(subject, predicate, value_of(geo_val(wkb)))
+                        }
+                        None => continue,
+                    }
+                }
+                Value::String(text) => api::NQuad::literal(subject, predicate, untyped(text)),
+                Value::Number(number) => match number.as_i64() {
+                    Some(int) => api::NQuad::literal(subject, predicate, int),
+                    None => match number.as_f64() {
+                        Some(float) => api::NQuad::literal(subject, predicate, float),
+                        None => continue,
+                    },
+                },
+                Value::Bool(boolean) => api::NQuad::literal(subject, predicate, *boolean),
+                Value::Null | Value::Array(_) => continue,
+            };
+
+            nquad.set_lang(lang.to_string());
+            for (facet, value) in &value_facets {
+                // Facets of list values come as a map from each value's index.
+                let value = match value {
+                    Value::Object(by_index) => by_index.get(&index.to_string()),
+                    value => Some(*value),
+                };
+                if let Some(value) = value {
+                    nquad.facets.extend(facet_value(facet, value));
+                }
+            }
+            nquads.push(nquad);
+        }
+    }
+
+    nquads
+}
+
+/// An untyped literal, which Dgraph converts to the predicate's schema type. Query results
+/// give datetimes, passwords and such as plain strings.
+fn untyped(text: &str) -> api::Value {
+    value_of(default_val(text.to_string()))
+}
+
+fn value_of(val: api::Value_oneof_val) -> api::Value {
+    api::Value {
+        val: Some(val),
+        ..Default::default()
+    }
+}
+
+/// The facets Dgraph returns next to `predicate` in `object`, as `predicate|facet` keys.
+fn facets<'a>(
+    object: &'a serde_json::Map<String, Value>,
+    predicate: &str,
+) -> Vec<(&'a str, &'a Value)> {
+    object
+        .iter()
+        .filter_map(|(key, value)| {
+            let facet = key.strip_prefix(predicate)?.strip_prefix('|')?;
+            Some((facet, value))
+        })
+        .collect()
+}
+
+/// A facet from its JSON value. Dgraph returns datetime facets as RFC 3339 strings, so strings
+/// holding a full date and time are taken as datetimes.
+fn facet_value(key: &str, value: &Value) -> Option<api::Facet> {
+    let value = match value {
+        Value::String(text) => match datetime::from_rfc3339(text) {
+            Some(time) if text.contains('T') => FacetValue::DateTime(time),
+            _ => FacetValue::String(text.clone()),
+        },
+        Value::Number(number) => match number.as_i64() {
+            Some(int) => FacetValue::Int(int),
+            None => FacetValue::Float(number.as_f64()?),
+        },
+        Value::Bool(boolean) => FacetValue::Bool(*boolean),
+        _ => return None,
+    };
+
+    Some(api::Facet::with_value(key, value))
+}
@@ -0,0 +1,172 @@
+//! Geo values in the little-endian well-known binary (WKB) form Dgraph stores them in, and
+//! their GeoJSON counterparts used in RDF.
+
+use serde_json::{json, Value};
+
+const POINT: u32 = 1;
+const POLYGON: u32 = 3;
+const MULTI_POLYGON: u32 = 6;
+
+pub(crate) fn point(point: [f64; 2]) -> Vec<u8> {
+    let mut wkb = header(POINT);
+    push_point(&mut wkb, point);
+    wkb
+}
+
+/// An outer ring followed by any holes, each ring closed by repeating its first point.
+pub(crate) fn polygon(rings: &[Vec<[f64; 2]>]) -> Vec<u8> {
+    let mut wkb = header(POLYGON);
+    wkb.extend_from_slice(&(rings.len() as u32).to_le_bytes());
+    for ring in rings {
+        wkb.extend_from_slice(&(ring.len() as u32).to_le_bytes());
+        for point in ring {
+            push_point(&mut wkb, *point);
+        }
+    }
+    wkb
+}
+
+pub(crate) fn multi_polygon(polygons: &[Vec<Vec<[f64; 2]>>]) -> Vec<u8> {
+    let mut wkb = header(MULTI_POLYGON);
+    wkb.extend_from_slice(&(polygons.len() as u32).to_le_bytes());
+    for rings in polygons {
+        wkb.extend(polygon(rings));
+    }
+    wkb
+}
+
+fn header(kind: u32) -> Vec<u8> {
+    let mut wkb = vec![1];
+    wkb.extend_from_slice(&kind.to_le_bytes());
+    wkb
+}
+
+fn push_point(wkb: &mut Vec<u8>, [longitude, latitude]: [f64; 2]) {
+    wkb.extend_from_slice(&longitude.to_le_bytes());
+    wkb.extend_from_slice(&latitude.to_le_bytes());
+}
+
+/// Encodes a GeoJSON `Point`, `Polygon` or `MultiPolygon` geometry.
+pub(crate) fn from_geojson(geojson: &Value) -> Option<Vec<u8>> {
+    let coordinates = geojson.get("coordinates")?;
+
+    match geojson.get("type")?.as_str()? {
+        "Point" => Some(point(json_point(coordinates)?)),
+        "Polygon" => Some(polygon(&json_rings(coordinates)?)),
+        "MultiPolygon" => Some(multi_polygon(
+            &coordinates
+                .as_array()?
+                .iter()
+                .map(json_rings)
+                .collect::<Option<Vec<_>>>()?,
+        )),
+        _ => None,
+    }
+}
+
+fn json_point(point: &Value) -> Option<[f64; 2]> {
+    match point.as_array()?.as_slice() {
+        [longitude, latitude] => Some([longitude.as_f64()?, latitude.as_f64()?]),
+        _ => None,
+    }
+}
+
+fn json_rings(rings: &Value) -> Option<Vec<Vec<[f64; 2]>>> {
+    rings
+        .as_array()?
+        .iter()
+        .map(|ring| ring.as_array()?.iter().map(json_point).collect())
+        .collect()
+}
+
+/// Decodes a `Point`, `Polygon` or `MultiPolygon` into a GeoJSON geometry.
+pub(crate) fn to_geojson(wkb: &[u8]) -> Option<Value> {
+    let mut reader = Reader { wkb, position: 0 };
+    let geometry = reader.geometry()?;
+
+    if reader.position == wkb.len() {
+        Some(geometry)
+    } else {
+        None
+    }
+}
+
+struct Reader<'a> {
+    wkb: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn geometry(&mut self) -> Option<Value> {
+        let little_endian = match self.take(1)?[0] {
+            0 => false,
+            1 => true,
+            _ => return None,
+        };
+
+        match self.u32(little_endian)? {
+            POINT => Some(json!({
+                "type": "Point",
+                "coordinates": self.point(little_endian)?,
+            })),
+            POLYGON => Some(json!({
+                "type": "Polygon",
+                "coordinates": self.rings(little_endian)?,
+            })),
+            MULTI_POLYGON => {
+                let count = self.u32(little_endian)?;
+                let polygons = (0..count)
+                    .map(|_| match self.geometry()? {
+                        Value::Object(mut polygon) if polygon["type"] == "Polygon" => {
+                            polygon.remove("coordinates")
+                        }
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+
+                Some(json!({ "type": "MultiPolygon", "coordinates": polygons }))
+            }
+            _ => None,
+        }
+    }
+
+    fn rings(&mut self, little_endian: bool) -> Option<Vec<Vec<[f64; 2]>>> {
+        let count = self.u32(little_endian)?;
+        (0..count)
+            .map(|_| {
+                let points = self.u32(little_endian)?;
+                (0..points).map(|_| self.point(little_endian)).collect()
+            })
+            .collect()
+    }
+
+    fn point(&mut self, little_endian: bool) -> Option<[f64; 2]> {
+        Some([self.f64(little_endian)?, self.f64(little_endian)?])
+    }
+
+    fn u32(&mut self, little_endian: bool) -> Option<u32> {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(self.take(4)?);
+        Some(if little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    }
+
+    fn f64(&mut self, little_endian: bool) -> Option<f64> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Some(if little_endian {
+            f64::from_le_bytes(bytes)
+        } else {
+            f64::from_be_bytes(bytes)
+        })
+    }
+
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.wkb.get(self.position..self.position + len)?;
+        self.position += len;
+        Some(bytes)
+    }
+}
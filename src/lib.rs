@@ -1,18 +1,56 @@
+mod balancer;
+mod builder;
 mod client;
+mod datetime;
+pub mod dql;
 mod errors;
+pub mod export;
+mod geo;
+pub mod loader;
+pub mod migrate;
+mod node;
+mod nquad;
+mod pages;
 mod protos;
+pub mod rdf;
+mod response;
+mod retry;
+pub mod schema;
 mod txn;
+mod uid;
+mod uidmap;
+mod upsert;
+mod vars;
 
 use grpcio::{ChannelBuilder, ChannelCredentialsBuilder, EnvBuilder};
 use std::sync::Arc;
 
 pub use grpcio;
 
+pub use balancer::{
+    Balancer, EndpointStats, LeastInFlight, Random, RoundRobin, DEFAULT_EJECTION_TIME,
+    DEFAULT_PROBE_TIMEOUT,
+};
+pub use builder::DgraphBuilder;
 pub use client::Dgraph;
 pub use errors::DgraphError;
+#[doc(hidden)]
+pub use node::__private;
+pub use node::{DgraphNode, DEFAULT_SELECTION_DEPTH};
+pub use nquad::FacetValue;
+pub use pages::Pages;
 pub use protos::api::*;
 pub use protos::api_grpc::*;
+pub use response::Assigned;
+pub use retry::RetryPolicy;
 pub use txn::Txn;
+pub use uid::Uid;
+pub use uidmap::UidMap;
+pub use upsert::{Upsert, UpsertResponse};
+pub use vars::Vars;
+
+#[cfg(feature = "derive")]
+pub use dgraph_derive::DgraphNode;
 
 #[cfg(feature = "with-serde")]
 extern crate serde;
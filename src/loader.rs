@@ -0,0 +1,559 @@
+//! Concurrent loading of large RDF and JSON inputs.
+//!
+//! A `Loader` reads its input on the calling thread, cuts it into batches and commits them
+//! from a pool of worker threads, each batch in its own transaction so requests spread
+//! across the client's endpoints. Aborted transactions are retried with the loader's
+//! `RetryPolicy`.
+//!
+//! Blank nodes keep their identity across batches: once the batch introducing `_:alice`
+//! commits, later batches refer to the UID it was given. A batch mentioning a blank node
+//! whose introducing batch is still in flight waits for it first.
+//!
+//! ```no_run
+//! use dgraph::loader::Loader;
+//!
+//! # fn main() -> Result<(), dgraph::DgraphError> {
+//! # let dgraph = dgraph::make_dgraph!(dgraph::new_dgraph_client("localhost:9080"));
+//! let progress = Loader::new()
+//!     .batch_size(1000)
+//!     .concurrency(8)
+//!     .on_progress(|progress| println!("{:.0} N-Quads/s", progress.rate()))
+//!     .load_file(&dgraph, "data.rdf.gz")?;
+//!
+//! println!("loaded {} N-Quads", progress.records);
+//! # Ok(())
+//! # }
+//! ```
+
+use flate2::read::MultiGzDecoder;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::mem;
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::client::Dgraph;
+use crate::errors::DgraphError;
+use crate::protos::api;
+use crate::rdf;
+use crate::retry::RetryPolicy;
+use crate::uidmap::{self, UidMap};
+
+pub const DEFAULT_BATCH_SIZE: usize = 1000;
+pub const DEFAULT_CONCURRENCY: usize = 4;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+type ProgressFn = dyn Fn(&Progress) + Send + Sync;
+
+/// The format of a loader's input.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    /// N-Quads, one statement per line.
+    Rdf,
+    /// JSON objects, either in top-level arrays or one per line.
+    Json,
+}
+
+impl Format {
+    /// Guesses the format from a file name such as `data.rdf.gz` or `people.json`.
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Format> {
+        let name = path.as_ref().file_name()?.to_str()?;
+        let name = name.trim_end_matches(".gz");
+
+        match name.rsplit('.').next()? {
+            "rdf" | "nq" | "nquads" => Some(Format::Rdf),
+            "json" | "ndjson" | "jsonl" => Some(Format::Json),
+            _ => None,
+        }
+    }
+}
+
+/// The state of a load, passed to the progress callback after every committed batch.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Progress {
+    /// N-Quads, or JSON objects, committed so far.
+    pub records: u64,
+    pub batches: u64,
+    pub elapsed: Duration,
+}
+
+impl Progress {
+    /// Records committed per second.
+    pub fn rate(&self) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs > 0.0 {
+            self.records as f64 / secs
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Loads RDF or JSON input in concurrent batches.
+#[derive(Clone)]
+pub struct Loader {
+    batch_size: usize,
+    concurrency: usize,
+    retry_policy: RetryPolicy,
+    on_progress: Option<Arc<ProgressFn>>,
+    uid_map: Option<Arc<Mutex<UidMap>>>,
+}
+
+impl Default for Loader {
+    fn default() -> Loader {
+        Loader {
+            batch_size: DEFAULT_BATCH_SIZE,
+            concurrency: DEFAULT_CONCURRENCY,
+            retry_policy: RetryPolicy::default(),
+            on_progress: None,
+            uid_map: None,
+        }
+    }
+}
+
+impl Loader {
+    pub fn new() -> Loader {
+        Default::default()
+    }
+
+    /// The number of N-Quads, or JSON objects, committed per transaction.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// The number of batches in flight at once.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Calls `f` from a worker thread after each committed batch. A panic in `f` stops the
+    /// load, which then fails with `DgraphError::LoaderPanicked`.
+    pub fn on_progress<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Progress) + Send + Sync + 'static,
+    {
+        self.on_progress = Some(Arc::new(f));
+        self
+    }
+
+    /// Resolves blank nodes and external ids through `map`, and records the UIDs assigned to
+    /// new ones in it, so loading the same data again reuses the nodes created before.
+    /// Without a map, blank nodes are only tracked for the duration of one load.
+    pub fn uid_map(mut self, map: UidMap) -> Self {
+        self.uid_map = Some(Arc::new(Mutex::new(map)));
+        self
+    }
+
+    /// Loads a file, picking the format from its name with `Format::from_path`.
+    pub fn load_file(
+        &self,
+        dgraph: &Dgraph,
+        path: impl AsRef<Path>,
+    ) -> Result<Progress, DgraphError> {
+        let path = path.as_ref();
+        let format = Format::from_path(path).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("cannot tell the format of {}", path.display()),
+            )
+        })?;
+
+        self.load(dgraph, File::open(path)?, format)
+    }
+
+    /// Loads `input`, which is decompressed first if it starts with the gzip magic bytes.
+    /// Returns once every batch is committed, or with the first error once the batches in
+    /// flight are done.
+    pub fn load(
+        &self,
+        dgraph: &Dgraph,
+        input: impl Read,
+        format: Format,
+    ) -> Result<Progress, DgraphError> {
+        let mut input = BufReader::new(input);
+        let input: Box<dyn BufRead> = if input.fill_buf()?.starts_with(&GZIP_MAGIC) {
+            Box::new(BufReader::new(MultiGzDecoder::new(input)))
+        } else {
+            Box::new(input)
+        };
+
+        // The map moves into the load's state and back, so loads sharing it run one at a time.
+        let mut uid_map = self.uid_map.as_ref().map(|uid_map| lock(uid_map));
+        let uids = uid_map
+            .as_mut()
+            .map(|uid_map| mem::take(&mut **uid_map))
+            .unwrap_or_default();
+
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                uids,
+                ..Default::default()
+            }),
+            resolved: Condvar::new(),
+            started: Instant::now(),
+            on_progress: self.on_progress.clone(),
+        });
+
+        let (sender, receiver) = mpsc::sync_channel(self.concurrency);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let workers: Vec<_> = (0..self.concurrency)
+            .map(|_| {
+                let dgraph = dgraph.clone();
+                let receiver = Arc::clone(&receiver);
+                let shared = Arc::clone(&shared);
+                let policy = self.retry_policy.clone();
+                thread::spawn(move || work(&dgraph, &receiver, &shared, &policy))
+            })
+            .collect();
+        // Only the workers hold the receiver, so sending fails once they have all stopped.
+        drop(receiver);
+
+        let mut dispatcher = Dispatcher {
+            sender,
+            shared: &shared,
+            batch_size: self.batch_size,
+        };
+        let read = match format {
+            Format::Rdf => dispatcher.read_rdf(input),
+            Format::Json => dispatcher.read_json(input),
+        };
+        drop(dispatcher);
+
+        let panicked = workers
+            .into_iter()
+            .fold(false, |panicked, worker| worker.join().is_err() || panicked);
+
+        let mut state = lock(&shared.state);
+        if panicked {
+            state.error.get_or_insert(DgraphError::LoaderPanicked);
+        }
+        if let Some(uid_map) = &mut uid_map {
+            **uid_map = mem::take(&mut state.uids);
+        }
+
+        read?;
+        match state.error.take() {
+            Some(err) => Err(err),
+            None => Ok(Progress {
+                elapsed: shared.started.elapsed(),
+                ..state.progress.clone()
+            }),
+        }
+    }
+}
+
+/// A batch's worth of input.
+enum Payload {
+    NQuads(Vec<api::NQuad>),
+    Json(Vec<serde_json::Value>),
+}
+
+impl Payload {
+    fn len(&self) -> usize {
+        match self {
+            Payload::NQuads(nquads) => nquads.len(),
+            Payload::Json(objects) => objects.len(),
+        }
+    }
+
+    /// The blank nodes and external ids mentioned, as named in `Response.uids`.
+    fn node_names(&self) -> HashSet<String> {
+        let mut names = HashSet::new();
+
+        match self {
+            Payload::NQuads(nquads) => {
+                for nquad in nquads {
+                    for node in &[&nquad.subject, &nquad.object_id] {
+                        if let Some(name) = uidmap::node_name(node) {
+                            names.insert(name.to_string());
+                        }
+                    }
+                }
+            }
+            Payload::Json(objects) => {
+                for object in objects {
+                    json_node_names(object, &mut names);
+                }
+            }
+        }
+
+        names
+    }
+
+    fn rewrite(&mut self, uids: &UidMap) {
+        match self {
+            Payload::NQuads(nquads) => uids.rewrite_nquads(nquads),
+            Payload::Json(objects) => {
+                for object in objects {
+                    uids.rewrite_json(object);
+                }
+            }
+        }
+    }
+
+    fn into_mutation(self) -> api::Mutation {
+        let mut mu = api::Mutation::new();
+        match self {
+            Payload::NQuads(nquads) => mu.set_set(nquads.into()),
+            Payload::Json(objects) => {
+                mu.set_set_json(serde_json::Value::Array(objects).to_string().into_bytes())
+            }
+        }
+        mu.set_commit_now(true);
+        mu
+    }
+}
+
+fn json_node_names(value: &serde_json::Value, names: &mut HashSet<String>) {
+    match value {
+        serde_json::Value::Object(object) => {
+            for (key, value) in object {
+                match value.as_str().and_then(uidmap::node_name) {
+                    Some(name) if key == "uid" => {
+                        names.insert(name.to_string());
+                    }
+                    _ => json_node_names(value, names),
+                }
+            }
+        }
+        serde_json::Value::Array(values) => {
+            for value in values {
+                json_node_names(value, names);
+            }
+        }
+        _ => (),
+    }
+}
+
+struct Batch {
+    mutation: api::Mutation,
+    records: usize,
+    /// Blank nodes and external ids this batch introduces, which other batches wait on.
+    claims: Vec<String>,
+}
+
+#[derive(Default)]
+struct State {
+    uids: UidMap,
+    claimed: HashSet<String>,
+    error: Option<DgraphError>,
+    progress: Progress,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    resolved: Condvar,
+    started: Instant,
+    on_progress: Option<Arc<ProgressFn>>,
+}
+
+impl Shared {
+    fn complete(&self, batch: &Batch, response: &api::Response) {
+        let progress = {
+            let mut state = lock(&self.state);
+            for name in &batch.claims {
+                state.claimed.remove(name);
+                let recorded = match response.uids.get(name) {
+                    Some(uid) => uid
+                        .parse()
+                        .and_then(|uid| state.uids.insert(name.as_str(), uid)),
+                    None => Ok(()),
+                };
+                if let Err(err) = recorded {
+                    state.error.get_or_insert(err);
+                }
+            }
+            if let Err(err) = state.uids.flush() {
+                state.error.get_or_insert(err);
+            }
+
+            state.progress.records += batch.records as u64;
+            state.progress.batches += 1;
+            state.progress.elapsed = self.started.elapsed();
+            state.progress.clone()
+        };
+        self.resolved.notify_all();
+
+        if let Some(on_progress) = &self.on_progress {
+            on_progress(&progress);
+        }
+    }
+
+    /// Gives up on a batch, recording `err` unless an earlier batch already failed.
+    fn fail(&self, batch: &Batch, err: Option<DgraphError>) {
+        {
+            let mut state = lock(&self.state);
+            for name in &batch.claims {
+                state.claimed.remove(name);
+            }
+            if state.error.is_none() {
+                state.error = err;
+            }
+        }
+        self.resolved.notify_all();
+    }
+
+    fn failed(&self) -> bool {
+        lock(&self.state).error.is_some()
+    }
+}
+
+fn work(dgraph: &Dgraph, receiver: &Mutex<Receiver<Batch>>, shared: &Shared, policy: &RetryPolicy) {
+    loop {
+        let batch = match lock(receiver).recv() {
+            Ok(batch) => batch,
+            Err(_) => return,
+        };
+
+        if shared.failed() {
+            shared.fail(&batch, None);
+            continue;
+        }
+
+        let _unwind = FailOnUnwind {
+            shared,
+            batch: &batch,
+        };
+        match dgraph.run_in_txn_with(policy, |txn| txn.mutate(batch.mutation.clone())) {
+            Ok(response) => shared.complete(&batch, &response),
+            Err(err) => shared.fail(&batch, Some(err)),
+        }
+    }
+}
+
+/// Fails the batch a worker is on if the worker panics, releasing its blank node claims so
+/// the dispatcher stops instead of waiting for them forever.
+struct FailOnUnwind<'a> {
+    shared: &'a Shared,
+    batch: &'a Batch,
+}
+
+impl Drop for FailOnUnwind<'_> {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            self.shared
+                .fail(self.batch, Some(DgraphError::LoaderPanicked));
+        }
+    }
+}
+
+/// Reads input on the calling thread and hands batches to the workers.
+struct Dispatcher<'a> {
+    sender: SyncSender<Batch>,
+    shared: &'a Shared,
+    batch_size: usize,
+}
+
+impl<'a> Dispatcher<'a> {
+    fn read_rdf(&mut self, input: impl BufRead) -> Result<(), DgraphError> {
+        let mut nquads = Vec::with_capacity(self.batch_size);
+
+        for (index, line) in input.lines().enumerate() {
+            let parsed = rdf::parse(&line?).map_err(|err| match err {
+                DgraphError::RdfSyntax {
+                    message, column, ..
+                } => DgraphError::RdfSyntax {
+                    message,
+                    line: index + 1,
+                    column,
+                },
+                err => err,
+            })?;
+
+            nquads.extend(parsed);
+            if nquads.len() >= self.batch_size {
+                let batch = mem::replace(&mut nquads, Vec::with_capacity(self.batch_size));
+                if !self.dispatch(Payload::NQuads(batch)) {
+                    return Ok(());
+                }
+            }
+        }
+
+        if !nquads.is_empty() {
+            self.dispatch(Payload::NQuads(nquads));
+        }
+        Ok(())
+    }
+
+    fn read_json(&mut self, input: impl BufRead) -> Result<(), DgraphError> {
+        let mut objects = Vec::with_capacity(self.batch_size);
+        let values = serde_json::Deserializer::from_reader(input).into_iter();
+
+        for value in values {
+            let value = value.map_err(|error| DgraphError::Deserialize {
+                error,
+                json: String::new(),
+            })?;
+
+            match value {
+                serde_json::Value::Array(values) => objects.extend(values),
+                value => objects.push(value),
+            }
+
+            while objects.len() >= self.batch_size {
+                let rest = objects.split_off(self.batch_size);
+                let batch = mem::replace(&mut objects, rest);
+                if !self.dispatch(Payload::Json(batch)) {
+                    return Ok(());
+                }
+            }
+        }
+
+        if !objects.is_empty() {
+            self.dispatch(Payload::Json(objects));
+        }
+        Ok(())
+    }
+
+    /// Claims the batch's new blank nodes and queues it, first waiting for batches in flight
+    /// that introduce blank nodes it mentions. Returns false once a batch has failed.
+    fn dispatch(&mut self, mut payload: Payload) -> bool {
+        let names = payload.node_names();
+
+        let mut state = lock(&self.shared.state);
+        while state.error.is_none() && names.iter().any(|name| state.claimed.contains(name)) {
+            state = self
+                .shared
+                .resolved
+                .wait(state)
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+        if state.error.is_some() {
+            return false;
+        }
+
+        payload.rewrite(&state.uids);
+        let claims: Vec<String> = names
+            .into_iter()
+            .filter(|name| state.uids.get(name).is_none())
+            .collect();
+        state.claimed.extend(claims.iter().cloned());
+        drop(state);
+
+        let batch = Batch {
+            records: payload.len(),
+            mutation: payload.into_mutation(),
+            claims,
+        };
+        self.sender.send(batch).is_ok()
+    }
+}
+
+/// Locks a mutex, carrying on with the data if a thread panicked while holding it.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
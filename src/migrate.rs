@@ -0,0 +1,526 @@
+//! Versioned schema and data migrations.
+//!
+//! Each `Migration` has a version, a name, any number of `alter` operations and any number of
+//! data steps run in one transaction. A `Migrator` applies the pending ones in version order
+//! and records each as a `DgraphMigration` node, in the same transaction as its data steps, so
+//! it is never applied twice. Runs hold a lock node created by an upsert, so two deployers
+//! cannot migrate the same cluster at the same time.
+//!
+//! ```no_run
+//! use dgraph::migrate::{Migration, Migrator};
+//!
+//! # fn main() -> Result<(), dgraph::DgraphError> {
+//! # let dgraph = dgraph::make_dgraph!(dgraph::new_dgraph_client("localhost:9080"));
+//! let migrator = Migrator::new()
+//!     .migration(Migration::new(1, "people").schema("name: string @index(exact) ."))
+//!     .migration(Migration::new(2, "admin").data(|txn| {
+//!         txn.set_json(&serde_json::json!({ "name": "admin" }))?;
+//!         Ok(())
+//!     }));
+//!
+//! let applied = migrator.run(&dgraph)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Alter operations are not transactional: if a run stops between a migration's `alter` and
+//! the commit of its record, the `alter` is run again next time, so keep them idempotent.
+
+use serde_derive::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::client::Dgraph;
+use crate::datetime;
+use crate::dql::{Block, Func, Query};
+use crate::errors::DgraphError;
+use crate::protos::api;
+use crate::schema::{Predicate, ScalarType, Schema, Tokenizer, TypeDef};
+use crate::txn::Txn;
+use crate::upsert::Upsert;
+use crate::vars::Vars;
+
+/// The `dgraph.type` of the nodes recording applied migrations.
+pub const MIGRATION_TYPE: &str = "DgraphMigration";
+
+/// The `dgraph.type` of the node held while migrations run.
+pub const LOCK_TYPE: &str = "DgraphMigrationLock";
+
+/// How long a lock is honoured before another run may take it over, in case its holder
+/// died without releasing it.
+pub const DEFAULT_LOCK_TTL: Duration = Duration::from_secs(10 * 60);
+
+const LOCK_NAME: &str = "migrations";
+
+type DataStep = Box<dyn Fn(&mut Txn) -> Result<(), DgraphError> + Send + Sync>;
+
+/// A single versioned migration.
+pub struct Migration {
+    version: u64,
+    name: String,
+    operations: Vec<api::Operation>,
+    data: Vec<DataStep>,
+}
+
+impl Migration {
+    pub fn new(version: u64, name: impl Into<String>) -> Migration {
+        Migration {
+            version,
+            name: name.into(),
+            operations: Vec::new(),
+            data: Vec::new(),
+        }
+    }
+
+    /// Adds an `alter` operation, run before the data steps.
+    pub fn alter(mut self, op: api::Operation) -> Self {
+        self.operations.push(op);
+        self
+    }
+
+    /// Adds an `alter` operation setting the given schema text.
+    pub fn schema(self, schema: impl Into<String>) -> Self {
+        self.alter(api::Operation {
+            schema: schema.into(),
+            ..Default::default()
+        })
+    }
+
+    /// Adds a data step. All data steps of a migration run in one transaction, which is
+    /// retried as a whole if it is aborted.
+    pub fn data<F>(mut self, step: F) -> Self
+    where
+        F: Fn(&mut Txn) -> Result<(), DgraphError> + Send + Sync + 'static,
+    {
+        self.data.push(Box::new(step));
+        self
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl fmt::Debug for Migration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Migration")
+            .field("version", &self.version)
+            .field("name", &self.name)
+            .field("operations", &self.operations.len())
+            .field("data", &self.data.len())
+            .finish()
+    }
+}
+
+/// A migration recorded in the graph as applied.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct AppliedMigration {
+    pub version: u64,
+    pub name: String,
+    /// When the migration was applied, in RFC 3339.
+    pub applied_at: String,
+}
+
+/// Applies an ordered list of migrations exactly once.
+#[derive(Debug)]
+pub struct Migrator {
+    migrations: Vec<Migration>,
+    lock_ttl: Duration,
+    owner: String,
+}
+
+impl Default for Migrator {
+    fn default() -> Migrator {
+        Migrator {
+            migrations: Vec::new(),
+            lock_ttl: DEFAULT_LOCK_TTL,
+            owner: format!("{}-{:016x}", std::process::id(), rand::random::<u64>()),
+        }
+    }
+}
+
+impl Migrator {
+    pub fn new() -> Migrator {
+        Default::default()
+    }
+
+    /// Appends a migration. Versions must be added in increasing order.
+    pub fn migration(mut self, migration: Migration) -> Self {
+        self.migrations.push(migration);
+        self
+    }
+
+    pub fn lock_ttl(mut self, ttl: Duration) -> Self {
+        self.lock_ttl = ttl;
+        self
+    }
+
+    /// Loads migrations from files named `<version>_<name>.<kind>` in `dir`, where `kind` is
+    /// `schema` for schema text applied with `alter`, `rdf` for N-Quads and `json` for JSON
+    /// to set. Files sharing a version make up one migration. Files not starting with a
+    /// version are skipped, and files of one version are read in name order.
+    pub fn from_dir(dir: impl AsRef<Path>) -> Result<Migrator, DgraphError> {
+        let mut migrations: BTreeMap<u64, Migration> = BTreeMap::new();
+
+        let mut paths = fs::read_dir(dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        paths.sort();
+
+        for path in paths {
+            let file_name = match path.file_name().and_then(|name| name.to_str()) {
+                Some(file_name) => file_name.to_string(),
+                None => continue,
+            };
+
+            let (stem, kind) = match file_name.rfind('.') {
+                Some(dot) => (&file_name[..dot], &file_name[dot + 1..]),
+                None => continue,
+            };
+            let (version, name) = match stem.find('_') {
+                Some(underscore) => match stem[..underscore].parse::<u64>() {
+                    Ok(version) => (version, &stem[underscore + 1..]),
+                    Err(_) => continue,
+                },
+                None => continue,
+            };
+
+            let migration = migrations
+                .remove(&version)
+                .unwrap_or_else(|| Migration::new(version, name));
+            if migration.name != name {
+                return Err(DgraphError::Migration(format!(
+                    "version {} is named both `{}` and `{}`",
+                    version, migration.name, name
+                )));
+            }
+
+            let content = fs::read(&path)?;
+            let migration = match kind {
+                "schema" => migration.schema(String::from_utf8_lossy(&content).into_owned()),
+                "rdf" => migration.data(move |txn| {
+                    let mut mu = api::Mutation::new();
+                    mu.set_set_nquads(content.clone());
+                    txn.mutate(mu)?;
+                    Ok(())
+                }),
+                "json" => migration.data(move |txn| {
+                    let mut mu = api::Mutation::new();
+                    mu.set_set_json(content.clone());
+                    txn.mutate(mu)?;
+                    Ok(())
+                }),
+                _ => {
+                    return Err(DgraphError::Migration(format!(
+                        "unknown migration file `{}`",
+                        file_name
+                    )))
+                }
+            };
+
+            migrations.insert(version, migration);
+        }
+
+        Ok(migrations
+            .into_iter()
+            .fold(Migrator::new(), |migrator, (_, migration)| {
+                migrator.migration(migration)
+            }))
+    }
+
+    pub fn migrations(&self) -> &[Migration] {
+        &self.migrations
+    }
+
+    /// The migrations recorded as applied, in version order.
+    pub fn applied(&self, dgraph: &Dgraph) -> Result<Vec<AppliedMigration>, DgraphError> {
+        let (query, vars) = Query::new()
+            .block(
+                Block::root("migrations", Func::dgraph_type(MIGRATION_TYPE))
+                    .field_as("version", "DgraphMigration.version")
+                    .field_as("name", "DgraphMigration.name")
+                    .field_as("applied_at", "DgraphMigration.appliedAt"),
+            )
+            .build()?;
+
+        let mut applied: Vec<AppliedMigration> = dgraph
+            .new_readonly_txn()?
+            .query_with_vars(&query, vars)?
+            .block_as("migrations")?;
+        applied.sort_by_key(|migration| migration.version);
+
+        Ok(applied)
+    }
+
+    /// The migrations that have not been applied yet, in the order they would run.
+    pub fn pending(&self, dgraph: &Dgraph) -> Result<Vec<&Migration>, DgraphError> {
+        let applied = self.applied(dgraph)?;
+        self.check(&applied)?;
+
+        Ok(self
+            .migrations
+            .iter()
+            .filter(|migration| {
+                !applied
+                    .iter()
+                    .any(|applied| applied.version == migration.version)
+            })
+            .collect())
+    }
+
+    /// Applies the pending migrations while holding the migration lock, returning the
+    /// versions applied. Fails with `DgraphError::MigrationLocked` if another run holds it.
+    pub fn run(&self, dgraph: &Dgraph) -> Result<Vec<u64>, DgraphError> {
+        dgraph.alter(&internal_schema().to_operation())?;
+        self.acquire_lock(dgraph)?;
+
+        let result = self.apply_pending(dgraph);
+        let released = self.release_lock(dgraph);
+
+        let applied = result?;
+        released?;
+        Ok(applied)
+    }
+
+    fn apply_pending(&self, dgraph: &Dgraph) -> Result<Vec<u64>, DgraphError> {
+        let mut applied = Vec::new();
+
+        for migration in self.pending(dgraph)? {
+            self.refresh_lock(dgraph)?;
+
+            for op in &migration.operations {
+                dgraph.alter(op)?;
+            }
+
+            dgraph.run_in_txn(|txn| {
+                for step in &migration.data {
+                    step(txn)?;
+                }
+
+                txn.set_json(&serde_json::json!({
+                    "uid": "_:migration",
+                    "dgraph.type": MIGRATION_TYPE,
+                    "DgraphMigration.version": migration.version,
+                    "DgraphMigration.name": migration.name,
+                    "DgraphMigration.appliedAt": datetime::to_rfc3339(SystemTime::now()),
+                }))?;
+
+                Ok(())
+            })?;
+
+            applied.push(migration.version);
+        }
+
+        Ok(applied)
+    }
+
+    /// Checks that versions increase and that applied versions kept their names.
+    fn check(&self, applied: &[AppliedMigration]) -> Result<(), DgraphError> {
+        for pair in self.migrations.windows(2) {
+            if pair[0].version >= pair[1].version {
+                return Err(DgraphError::Migration(format!(
+                    "version {} follows version {}",
+                    pair[1].version, pair[0].version
+                )));
+            }
+        }
+
+        for applied in applied {
+            let known = self
+                .migrations
+                .iter()
+                .find(|migration| migration.version == applied.version);
+
+            if let Some(migration) = known {
+                if migration.name != applied.name {
+                    return Err(DgraphError::Migration(format!(
+                        "version {} was applied as `{}` but is now named `{}`",
+                        applied.version, applied.name, migration.name
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Creates the lock node, or takes it over once it is older than the lock TTL. The
+    /// `@upsert` index on the lock name makes concurrent attempts conflict, so only one of
+    /// them commits.
+    fn acquire_lock(&self, dgraph: &Dgraph) -> Result<(), DgraphError> {
+        let now = SystemTime::now();
+        let cutoff = now.checked_sub(self.lock_ttl).unwrap_or(UNIX_EPOCH);
+
+        let upsert = Upsert::new(
+            r#"query q($name: string, $cutoff: string) {
+                lock as var(func: eq(DgraphMigrationLock.name, $name))
+                stale as var(func: uid(lock))
+                    @filter(lt(DgraphMigrationLock.acquiredAt, $cutoff))
+                holder(func: uid(lock)) { owner: DgraphMigrationLock.owner }
+                stale(func: uid(stale)) { uid }
+            }"#,
+        )
+        .vars(
+            Vars::new()
+                .string("$name", LOCK_NAME)
+                .datetime("$cutoff", cutoff),
+        )
+        .set_json(&serde_json::json!({
+            "uid": "_:lock",
+            "dgraph.type": LOCK_TYPE,
+            "DgraphMigrationLock.name": LOCK_NAME,
+            "DgraphMigrationLock.owner": self.owner,
+            "DgraphMigrationLock.acquiredAt": datetime::to_rfc3339(now),
+        }))?
+        .when("@if(eq(len(lock), 0))")
+        .set_json(&serde_json::json!({
+            "uid": "uid(stale)",
+            "DgraphMigrationLock.owner": self.owner,
+            "DgraphMigrationLock.acquiredAt": datetime::to_rfc3339(now),
+        }))?
+        .when("@if(eq(len(stale), 1))")
+        .commit_now();
+
+        let response = match dgraph.new_txn()?.upsert(upsert) {
+            Ok(response) => response,
+            Err(DgraphError::TxnAborted) => {
+                return Err(DgraphError::MigrationLocked(
+                    "a concurrent migration run".to_string(),
+                ))
+            }
+            Err(err) => return Err(err),
+        };
+
+        let state: LockState = response.response().json_as()?;
+        match (state.holder.first(), state.stale.is_empty()) {
+            (Some(holder), true) => Err(DgraphError::MigrationLocked(holder.owner.clone())),
+            _ => Ok(()),
+        }
+    }
+
+    /// Renews `acquiredAt` of the lock in the same upsert that checks it is still held by
+    /// this run, so a run that outlived the lock TTL stops before another one takes over.
+    fn refresh_lock(&self, dgraph: &Dgraph) -> Result<(), DgraphError> {
+        let upsert = Upsert::new(
+            r#"query q($name: string, $owner: string) {
+                lock as var(func: eq(DgraphMigrationLock.name, $name))
+                mine as var(func: uid(lock)) @filter(eq(DgraphMigrationLock.owner, $owner))
+                holder(func: uid(lock)) { owner: DgraphMigrationLock.owner }
+                mine(func: uid(mine)) { uid }
+            }"#,
+        )
+        .vars(
+            Vars::new()
+                .string("$name", LOCK_NAME)
+                .string("$owner", self.owner.as_str()),
+        )
+        .set_json(&serde_json::json!({
+            "uid": "uid(mine)",
+            "DgraphMigrationLock.acquiredAt": datetime::to_rfc3339(SystemTime::now()),
+        }))?
+        .when("@if(eq(len(mine), 1))")
+        .commit_now();
+
+        let response = match dgraph.new_txn()?.upsert(upsert) {
+            Ok(response) => response,
+            Err(DgraphError::TxnAborted) => {
+                return Err(DgraphError::MigrationLocked(
+                    "a concurrent migration run".to_string(),
+                ))
+            }
+            Err(err) => return Err(err),
+        };
+
+        let state: LockState = response.response().json_as()?;
+        if !state.mine.is_empty() {
+            return Ok(());
+        }
+
+        let holder = state.holder.first().map(|holder| holder.owner.clone());
+        Err(DgraphError::MigrationLocked(holder.unwrap_or_else(|| {
+            "nobody, the lock was released".to_string()
+        })))
+    }
+
+    fn release_lock(&self, dgraph: &Dgraph) -> Result<(), DgraphError> {
+        let upsert = Upsert::new(
+            r#"query q($name: string, $owner: string) {
+                lock as var(func: eq(DgraphMigrationLock.name, $name))
+                    @filter(eq(DgraphMigrationLock.owner, $owner))
+            }"#,
+        )
+        .vars(
+            Vars::new()
+                .string("$name", LOCK_NAME)
+                .string("$owner", self.owner.as_str()),
+        )
+        .delete_nquads("uid(lock) * * .")
+        .commit_now();
+
+        dgraph.new_txn()?.upsert(upsert)?;
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+struct LockState {
+    #[serde(default)]
+    holder: Vec<LockHolder>,
+    #[serde(default)]
+    stale: Vec<serde_json::Value>,
+    #[serde(default)]
+    mine: Vec<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct LockHolder {
+    #[serde(default)]
+    owner: String,
+}
+
+/// The predicates and types holding migration records and the lock.
+fn internal_schema() -> Schema {
+    Schema::new()
+        .predicate(
+            Predicate::new("DgraphMigration.version", ScalarType::Int)
+                .index(vec![Tokenizer::Int])
+                .upsert(),
+        )
+        .predicate(Predicate::new("DgraphMigration.name", ScalarType::String))
+        .predicate(Predicate::new(
+            "DgraphMigration.appliedAt",
+            ScalarType::DateTime,
+        ))
+        .predicate(
+            Predicate::new("DgraphMigrationLock.name", ScalarType::String)
+                .index(vec![Tokenizer::Exact])
+                .upsert(),
+        )
+        .predicate(
+            Predicate::new("DgraphMigrationLock.owner", ScalarType::String)
+                .index(vec![Tokenizer::Exact]),
+        )
+        .predicate(
+            Predicate::new("DgraphMigrationLock.acquiredAt", ScalarType::DateTime)
+                .index(vec![Tokenizer::Hour]),
+        )
+        .type_def(
+            TypeDef::new(MIGRATION_TYPE)
+                .field("DgraphMigration.version")
+                .field("DgraphMigration.name")
+                .field("DgraphMigration.appliedAt"),
+        )
+        .type_def(
+            TypeDef::new(LOCK_TYPE)
+                .field("DgraphMigrationLock.name")
+                .field("DgraphMigrationLock.owner")
+                .field("DgraphMigrationLock.acquiredAt"),
+        )
+}
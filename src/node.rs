@@ -0,0 +1,98 @@
+use crate::errors::DgraphError;
+
+/// Default number of edge levels `DgraphNode::selection` expands.
+pub const DEFAULT_SELECTION_DEPTH: usize = 2;
+
+/// A Rust type stored as Dgraph nodes of a single `dgraph.type`, usually implemented with
+/// `#[derive(DgraphNode)]` so the schema, queries and mutations all follow one struct.
+///
+/// ```no_run
+/// use dgraph::DgraphNode;
+/// use serde_derive::Deserialize;
+///
+/// #[derive(DgraphNode, Deserialize)]
+/// struct Person {
+///     uid: Option<dgraph::Uid>,
+///     #[dgraph(index(exact, term))]
+///     name: String,
+///     age: Option<u8>,
+///     #[dgraph(edge, reverse, count)]
+///     friend: Option<Vec<Person>>,
+/// }
+///
+/// # fn main() -> Result<(), dgraph::DgraphError> {
+/// # let dgraph = dgraph::make_dgraph!(dgraph::new_dgraph_client("localhost:9080"));
+/// dgraph.alter(&dgraph::Operation {
+///     schema: Person::schema(),
+///     ..Default::default()
+/// })?;
+///
+/// let query = format!("{{ people(func: type(Person)) {} }}", Person::selection());
+/// let people: Vec<Person> = dgraph.new_readonly_txn()?.query(&query)?.block_as("people")?;
+/// # Ok(())
+/// # }
+/// ```
+pub trait DgraphNode {
+    /// The `dgraph.type` given to the nodes.
+    const TYPE_NAME: &'static str;
+
+    /// The schema of the node's predicates followed by its type definition, ready to be
+    /// passed to `Dgraph::alter`. Edge targets have schemas of their own.
+    fn schema() -> String;
+
+    /// A selection set such as `{ uid name friend { uid name } }`, with edges expanded
+    /// `depth` levels deep. Predicates named differently from their field are aliased to
+    /// the field name, so the result deserializes back into the struct.
+    fn selection_to_depth(depth: usize) -> String;
+
+    /// The selection set with edges expanded `DEFAULT_SELECTION_DEPTH` levels deep.
+    fn selection() -> String {
+        Self::selection_to_depth(DEFAULT_SELECTION_DEPTH)
+    }
+
+    /// The node as mutation JSON, including its `dgraph.type`. A UID that is unset or empty
+    /// is left out so a new node is created; a `_:name` blank node is kept.
+    fn to_mutation_json(&self) -> Result<serde_json::Value, DgraphError>;
+}
+
+/// Helpers for the code generated by `#[derive(DgraphNode)]`.
+#[doc(hidden)]
+pub mod __private {
+    use serde::Serialize;
+
+    use super::DgraphNode;
+    use crate::errors::DgraphError;
+
+    pub use serde_json::{Map, Value};
+
+    pub fn to_json<T: Serialize + ?Sized>(value: &T) -> Result<Value, DgraphError> {
+        serde_json::to_value(value).map_err(DgraphError::Serialize)
+    }
+
+    pub fn insert(object: &mut Map<String, Value>, predicate: &str, value: Value) {
+        if !value.is_null() {
+            object.insert(predicate.to_string(), value);
+        }
+    }
+
+    pub fn insert_uid(object: &mut Map<String, Value>, uid: Value) {
+        match uid {
+            Value::Null => (),
+            Value::String(ref uid) if uid.is_empty() => (),
+            uid => {
+                object.insert("uid".to_string(), uid);
+            }
+        }
+    }
+
+    pub fn edges<'a, T, I>(nodes: I) -> Result<Value, DgraphError>
+    where
+        T: DgraphNode + 'a,
+        I: Iterator<Item = &'a T>,
+    {
+        nodes
+            .map(DgraphNode::to_mutation_json)
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::Array)
+    }
+}
@@ -0,0 +1,257 @@
+use std::time::SystemTime;
+
+use crate::datetime;
+use crate::geo;
+use crate::protos::api;
+use crate::uid::Uid;
+
+/// Token prefix Dgraph's term tokenizer gives to string facet tokens.
+const TERM_TOKEN_PREFIX: char = '\u{1}';
+
+/// Builders for N-Quads passed through `Mutation.set` and `Mutation.del`, as an alternative to
+/// RDF text or JSON.
+///
+/// ```
+/// use dgraph::{Mutation, NQuad, Uid};
+/// use std::time::SystemTime;
+///
+/// let mut mu = Mutation::new();
+/// mu.set.push(NQuad::literal("_:alice", "name", "Alice"));
+/// mu.set.push(NQuad::literal("_:alice", "name", "Alicia").lang("es"));
+/// mu.set.push(NQuad::edge("_:alice", "friend", Uid(0x2a)).facet("since", SystemTime::now()));
+/// ```
+impl api::NQuad {
+    /// A predicate holding a value, such as a string, number, `SystemTime` or geo value.
+    pub fn literal(
+        subject: impl Into<String>,
+        predicate: impl Into<String>,
+        value: impl Into<api::Value>,
+    ) -> api::NQuad {
+        let mut nquad = api::NQuad::new();
+        nquad.set_subject(subject.into());
+        nquad.set_predicate(predicate.into());
+        nquad.set_object_value(value.into());
+        nquad
+    }
+
+    /// An edge to another node, given as a `Uid` or a `_:name` blank node.
+    pub fn edge(
+        subject: impl Into<String>,
+        predicate: impl Into<String>,
+        object: impl Into<String>,
+    ) -> api::NQuad {
+        let mut nquad = api::NQuad::new();
+        nquad.set_subject(subject.into());
+        nquad.set_predicate(predicate.into());
+        nquad.set_object_id(object.into());
+        nquad
+    }
+
+    /// Tags a string literal with a language, for predicates with `@lang`.
+    pub fn lang(mut self, lang: impl Into<String>) -> Self {
+        self.set_lang(lang.into());
+        self
+    }
+
+    /// Adds a facet to the edge or value.
+    pub fn facet(mut self, key: impl Into<String>, value: impl Into<FacetValue>) -> Self {
+        self.facets.push(api::Facet::with_value(key, value));
+        self
+    }
+}
+
+impl api::Value {
+    pub fn string(value: impl Into<String>) -> api::Value {
+        value_of(api::Value_oneof_val::str_val(value.into()))
+    }
+
+    pub fn int(value: i64) -> api::Value {
+        value_of(api::Value_oneof_val::int_val(value))
+    }
+
+    pub fn float(value: f64) -> api::Value {
+        value_of(api::Value_oneof_val::double_val(value))
+    }
+
+    pub fn bool(value: bool) -> api::Value {
+        value_of(api::Value_oneof_val::bool_val(value))
+    }
+
+    pub fn bytes(value: Vec<u8>) -> api::Value {
+        value_of(api::Value_oneof_val::bytes_val(value))
+    }
+
+    /// A `datetime`, encoded the way Dgraph stores it.
+    pub fn datetime(value: SystemTime) -> api::Value {
+        value_of(api::Value_oneof_val::datetime_val(datetime::to_binary(
+            value,
+        )))
+    }
+
+    /// A plain-text password for a `password` predicate. The server hashes it.
+    pub fn password(value: impl Into<String>) -> api::Value {
+        value_of(api::Value_oneof_val::password_val(value.into()))
+    }
+
+    pub fn uid(value: impl Into<Uid>) -> api::Value {
+        value_of(api::Value_oneof_val::uid_val(value.into().as_u64()))
+    }
+
+    /// A `geo` point at `[longitude, latitude]`.
+    pub fn geo_point(point: [f64; 2]) -> api::Value {
+        value_of(api::Value_oneof_val::geo_val(geo::point(point)))
+    }
+
+    /// A `geo` polygon: an outer ring followed by any holes, each ring closed by repeating its
+    /// first point.
+    pub fn geo_polygon(rings: Vec<Vec<[f64; 2]>>) -> api::Value {
+        value_of(api::Value_oneof_val::geo_val(geo::polygon(&rings)))
+    }
+
+    pub fn geo_multi_polygon(polygons: Vec<Vec<Vec<[f64; 2]>>>) -> api::Value {
+        value_of(api::Value_oneof_val::geo_val(geo::multi_polygon(&polygons)))
+    }
+}
+
+fn value_of(val: api::Value_oneof_val) -> api::Value {
+    api::Value {
+        val: Some(val),
+        ..Default::default()
+    }
+}
+
+impl From<&str> for api::Value {
+    fn from(value: &str) -> api::Value {
+        api::Value::string(value)
+    }
+}
+
+impl From<String> for api::Value {
+    fn from(value: String) -> api::Value {
+        api::Value::string(value)
+    }
+}
+
+impl From<i64> for api::Value {
+    fn from(value: i64) -> api::Value {
+        api::Value::int(value)
+    }
+}
+
+impl From<f64> for api::Value {
+    fn from(value: f64) -> api::Value {
+        api::Value::float(value)
+    }
+}
+
+impl From<bool> for api::Value {
+    fn from(value: bool) -> api::Value {
+        api::Value::bool(value)
+    }
+}
+
+impl From<SystemTime> for api::Value {
+    fn from(value: SystemTime) -> api::Value {
+        api::Value::datetime(value)
+    }
+}
+
+impl From<Uid> for api::Value {
+    fn from(value: Uid) -> api::Value {
+        api::Value::uid(value)
+    }
+}
+
+/// The value of a facet.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FacetValue {
+    String(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    DateTime(SystemTime),
+}
+
+impl From<&str> for FacetValue {
+    fn from(value: &str) -> FacetValue {
+        FacetValue::String(value.to_string())
+    }
+}
+
+impl From<String> for FacetValue {
+    fn from(value: String) -> FacetValue {
+        FacetValue::String(value)
+    }
+}
+
+impl From<i64> for FacetValue {
+    fn from(value: i64) -> FacetValue {
+        FacetValue::Int(value)
+    }
+}
+
+impl From<f64> for FacetValue {
+    fn from(value: f64) -> FacetValue {
+        FacetValue::Float(value)
+    }
+}
+
+impl From<bool> for FacetValue {
+    fn from(value: bool) -> FacetValue {
+        FacetValue::Bool(value)
+    }
+}
+
+impl From<SystemTime> for FacetValue {
+    fn from(value: SystemTime) -> FacetValue {
+        FacetValue::DateTime(value)
+    }
+}
+
+impl api::Facet {
+    /// A facet with its value encoded the way Dgraph stores it. String facets also carry the
+    /// term tokens that facet filters such as `allofterms` match against.
+    pub fn with_value(key: impl Into<String>, value: impl Into<FacetValue>) -> api::Facet {
+        let mut facet = api::Facet::new();
+        facet.set_key(key.into());
+
+        match value.into() {
+            FacetValue::String(value) => {
+                facet.set_val_type(api::Facet_ValType::STRING);
+                facet.set_tokens(term_tokens(&value).into());
+                facet.set_value(value.into_bytes());
+            }
+            FacetValue::Int(value) => {
+                facet.set_val_type(api::Facet_ValType::INT);
+                facet.set_value(value.to_le_bytes().to_vec());
+            }
+            FacetValue::Float(value) => {
+                facet.set_val_type(api::Facet_ValType::FLOAT);
+                facet.set_value(value.to_le_bytes().to_vec());
+            }
+            FacetValue::Bool(value) => {
+                facet.set_val_type(api::Facet_ValType::BOOL);
+                facet.set_value(vec![value as u8]);
+            }
+            FacetValue::DateTime(value) => {
+                facet.set_val_type(api::Facet_ValType::DATETIME);
+                facet.set_value(datetime::to_binary(value));
+            }
+        }
+
+        facet
+    }
+}
+
+/// Lowercased words of `text`, sorted and deduplicated, each with the term tokenizer prefix.
+fn term_tokens(text: &str) -> Vec<String> {
+    let mut tokens: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| format!("{}{}", TERM_TOKEN_PREFIX, word.to_lowercase()))
+        .collect();
+
+    tokens.sort();
+    tokens.dedup();
+    tokens
+}
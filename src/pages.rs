@@ -0,0 +1,105 @@
+use serde::de::{Deserialize, DeserializeOwned};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+use crate::errors::DgraphError;
+use crate::response::truncate;
+use crate::txn::Txn;
+use crate::uid::Uid;
+use crate::vars::Vars;
+
+/// The cursor before the first page.
+const FIRST_CURSOR: Uid = Uid(0);
+
+/// Pages of a query's results, walked by UID cursor. Returned by `Txn::paginate`.
+///
+/// Every page is queried in the same transaction, at its `start_ts`, so pages see one
+/// consistent snapshot: nodes created or deleted meanwhile neither appear twice nor shift
+/// later pages, as they would with `offset`.
+pub struct Pages<'a, T> {
+    txn: &'a mut Txn,
+    query: String,
+    vars: Vars,
+    page_size: i64,
+    after: Option<Uid>,
+    nodes: PhantomData<fn() -> T>,
+}
+
+impl<'a, T: DeserializeOwned> Pages<'a, T> {
+    pub(crate) fn new(txn: &'a mut Txn, query: String, vars: Vars, page_size: i64) -> Self {
+        Pages {
+            txn,
+            query,
+            vars,
+            page_size: page_size.max(1),
+            after: Some(FIRST_CURSOR),
+            nodes: PhantomData,
+        }
+    }
+
+    /// Queries the page after `after`, returning its nodes and the cursor of the next page,
+    /// if there may be one.
+    fn page(&mut self, after: Uid) -> Result<(Vec<T>, Option<Uid>), DgraphError> {
+        let vars = self
+            .vars
+            .clone()
+            .int("$first", self.page_size)
+            .uid("$after", after);
+        vars.check_header(&self.query)?;
+        let blocks: HashMap<String, Vec<serde_json::Value>> =
+            self.txn.query_with_vars(&self.query, vars)?.json_as()?;
+
+        if blocks.len() > 1 {
+            return Err(DgraphError::InvalidDql(
+                "paginated queries must have a single query block".to_string(),
+            ));
+        }
+        let page = blocks
+            .into_iter()
+            .next()
+            .map_or_else(Vec::new, |(_, page)| page);
+
+        let next = match page.last() {
+            Some(last) if page.len() as i64 >= self.page_size => {
+                match last.get("uid").and_then(serde_json::Value::as_str) {
+                    Some(uid) => Some(uid.parse()?),
+                    None => {
+                        return Err(DgraphError::InvalidDql(
+                            "paginated queries must select `uid`".to_string(),
+                        ))
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        let nodes = page
+            .iter()
+            .map(|node| {
+                T::deserialize(node).map_err(|error| DgraphError::Deserialize {
+                    json: truncate(&node.to_string()),
+                    error,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok((nodes, next))
+    }
+}
+
+impl<'a, T: DeserializeOwned> Iterator for Pages<'a, T> {
+    type Item = Result<Vec<T>, DgraphError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let after = self.after.take()?;
+
+        match self.page(after) {
+            Ok((nodes, _)) if nodes.is_empty() => None,
+            Ok((nodes, next)) => {
+                self.after = next;
+                Some(Ok(nodes))
+            }
+            Err(error) => Some(Err(error)),
+        }
+    }
+}
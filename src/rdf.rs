@@ -0,0 +1,537 @@
+//! Conversion between RDF N-Quad text, as taken by `Mutation.set_nquads`, and the structured
+//! `api::NQuad`s of `Mutation.set`.
+//!
+//! ```
+//! use dgraph::rdf;
+//!
+//! # fn main() -> Result<(), dgraph::DgraphError> {
+//! let nquads = rdf::parse(
+//!     r#"_:alice <name> "Alicia"@es .
+//!        _:alice <born> "1990-05-01"^^<xs:dateTime> .
+//!        _:alice <friend> <0x2a> (since=2006-01-02T15:04:05, close=true) ."#,
+//! )?;
+//!
+//! assert_eq!(nquads.len(), 3);
+//! assert_eq!(
+//!     rdf::to_string(&nquads[..1]),
+//!     "_:alice <name> \"Alicia\"@es .\n"
+//! );
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Typed literals become the matching `api::Value` variant, with datetimes and GeoJSON
+//! encoded the way Dgraph stores them; untyped literals are left to the predicate's schema
+//! type. A `*` predicate or object, as used in deletes, becomes `_STAR_ALL`.
+
+use crate::datetime;
+use crate::errors::DgraphError;
+use crate::geo;
+use crate::nquad::FacetValue;
+use crate::protos::api;
+
+/// The predicate or object value Dgraph uses for a `*` wildcard.
+pub const STAR_ALL: &str = "_STAR_ALL";
+
+/// Parses N-Quad text into `api::NQuad`s. Statements end with ` .` and `#` starts a comment.
+pub fn parse(text: &str) -> Result<Vec<api::NQuad>, DgraphError> {
+    let mut parser = Parser::new(text);
+    let mut nquads = Vec::new();
+
+    while parser.skip_space() {
+        nquads.push(parser.nquad()?);
+    }
+
+    Ok(nquads)
+}
+
+/// Writes `api::NQuad`s as N-Quad text, one statement per line, such that `parse` reads them
+/// back.
+pub fn to_string(nquads: &[api::NQuad]) -> String {
+    nquads.iter().map(write_nquad).collect()
+}
+
+fn write_nquad(nquad: &api::NQuad) -> String {
+    let mut line = format!("{} ", write_node(&nquad.subject));
+
+    if nquad.predicate == STAR_ALL {
+        line.push('*');
+    } else {
+        line.push_str(&format!("<{}>", nquad.predicate));
+    }
+    line.push(' ');
+
+    match nquad
+        .object_value
+        .as_ref()
+        .and_then(|value| value.val.as_ref())
+    {
+        _ if !nquad.object_id.is_empty() => line.push_str(&write_node(&nquad.object_id)),
+        Some(val) => line.push_str(&write_value(val, &nquad.lang)),
+        None => line.push_str("\"\""),
+    }
+
+    if !nquad.label.is_empty() {
+        line.push_str(&format!(" <{}>", nquad.label));
+    }
+
+    if !nquad.facets.is_empty() {
+        let facets: Vec<String> = nquad.facets.iter().map(write_facet).collect();
+        line.push_str(&format!(" ({})", facets.join(", ")));
+    }
+
+    line.push_str(" .\n");
+    line
+}
+
+/// Writes a subject or object node: blank nodes and `uid(v)`/`val(v)` variables as they are,
+/// anything else as an IRI.
+fn write_node(node: &str) -> String {
+    if node.starts_with("_:") || node.starts_with("uid(") || node.starts_with("val(") {
+        node.to_string()
+    } else {
+        format!("<{}>", node)
+    }
+}
+
+fn write_value(val: &api::Value_oneof_val, lang: &str) -> String {
+    use api::Value_oneof_val::*;
+
+    let typed = |text: String, kind: &str| format!("{}^^<{}>", quote(&text), kind);
+
+    match val {
+        default_val(star) if star == STAR_ALL => "*".to_string(),
+        default_val(text) | str_val(text) if !lang.is_empty() => {
+            format!("{}@{}", quote(text), lang)
+        }
+        default_val(text) => quote(text),
+        str_val(text) => typed(text.clone(), "xs:string"),
+        int_val(int) => typed(int.to_string(), "xs:int"),
+        double_val(float) => typed(format!("{:?}", float), "xs:float"),
+        bool_val(boolean) => typed(boolean.to_string(), "xs:boolean"),
+        password_val(password) => typed(password.clone(), "xs:password"),
+        uid_val(uid) => format!("<{:#x}>", uid),
+        datetime_val(bytes) | date_val(bytes) => match datetime::from_binary(bytes) {
+            Some(time) => typed(datetime::to_rfc3339(time), "xs:dateTime"),
+            None => quote(&String::from_utf8_lossy(bytes)),
+        },
+        geo_val(wkb) => match geo::to_geojson(wkb) {
+            Some(geojson) => typed(geojson.to_string(), "geo:geojson"),
+            None => quote(&String::from_utf8_lossy(wkb)),
+        },
+        bytes_val(bytes) => quote(&String::from_utf8_lossy(bytes)),
+    }
+}
+
+fn write_facet(facet: &api::Facet) -> String {
+    let value = &facet.value;
+    let value = match facet.val_type {
+        api::Facet_ValType::STRING => quote(&String::from_utf8_lossy(value)),
+        api::Facet_ValType::INT => match le_bytes(value) {
+            Some(bytes) => i64::from_le_bytes(bytes).to_string(),
+            None => quote(&String::from_utf8_lossy(value)),
+        },
+        api::Facet_ValType::FLOAT => match le_bytes(value) {
+            Some(bytes) => format!("{:?}", f64::from_le_bytes(bytes)),
+            None => quote(&String::from_utf8_lossy(value)),
+        },
+        api::Facet_ValType::BOOL => (value.first() == Some(&1)).to_string(),
+        api::Facet_ValType::DATETIME => match datetime::from_binary(value) {
+            Some(time) => datetime::to_rfc3339(time),
+            None => quote(&String::from_utf8_lossy(value)),
+        },
+    };
+
+    format!("{}={}", facet.key, value)
+}
+
+fn le_bytes(value: &[u8]) -> Option<[u8; 8]> {
+    if value.len() != 8 {
+        return None;
+    }
+
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(value);
+    Some(bytes)
+}
+
+fn quote(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+    line: usize,
+    column: usize,
+}
+
+impl Parser {
+    fn new(text: &str) -> Parser {
+        Parser {
+            chars: text.chars().collect(),
+            position: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    fn nquad(&mut self) -> Result<api::NQuad, DgraphError> {
+        let mut nquad = api::NQuad::new();
+
+        nquad.set_subject(self.node("a subject")?);
+
+        self.skip_space();
+        if self.eat('*') {
+            nquad.set_predicate(STAR_ALL.to_string());
+        } else if self.peek() == Some('<') {
+            nquad.set_predicate(self.iri()?);
+        } else {
+            return Err(self.error("expected a predicate"));
+        }
+
+        self.skip_space();
+        match self.peek() {
+            Some('"') => {
+                let (value, lang) = self.literal()?;
+                nquad.set_object_value(value);
+                nquad.set_lang(lang);
+            }
+            Some('*') => {
+                self.bump();
+                nquad.set_object_value(api::Value {
+                    val: Some(api::Value_oneof_val::default_val(STAR_ALL.to_string())),
+                    ..Default::default()
+                });
+            }
+            _ => nquad.set_object_id(self.node("an object")?),
+        }
+
+        self.skip_space();
+        if self.peek() == Some('<') {
+            nquad.set_label(self.iri()?);
+            self.skip_space();
+        }
+
+        if self.eat('(') {
+            nquad.set_facets(self.facets()?.into());
+            self.skip_space();
+        }
+
+        if !self.eat('.') {
+            return Err(self.error("expected `.` at the end of the statement"));
+        }
+
+        Ok(nquad)
+    }
+
+    /// A subject or object node: `<iri>`, `_:blank`, `uid(v)` or `val(v)`.
+    fn node(&mut self, what: &str) -> Result<String, DgraphError> {
+        self.skip_space();
+
+        if self.peek() == Some('<') {
+            return self.iri();
+        }
+
+        let (line, column) = (self.line, self.column);
+        let rest: String = self.chars[self.position..].iter().take(4).collect();
+
+        if rest.starts_with("_:") {
+            let mut name = String::new();
+            while let Some(c) = self.peek() {
+                if c.is_whitespace() || "<>\"()".contains(c) {
+                    break;
+                }
+                name.push(c);
+                self.bump();
+            }
+
+            // A statement may end right after a blank node, as in `_:a <p> _:b.`.
+            if name.ends_with('.') {
+                name.pop();
+                self.position -= 1;
+                self.column -= 1;
+            }
+
+            if name.len() > 2 {
+                return Ok(name);
+            }
+        } else if rest == "uid(" || rest == "val(" {
+            let mut name = String::new();
+            while let Some(c) = self.bump() {
+                name.push(c);
+                if c == ')' {
+                    return Ok(name);
+                }
+            }
+        }
+
+        Err(error_at(&format!("expected {}", what), line, column))
+    }
+
+    fn iri(&mut self) -> Result<String, DgraphError> {
+        let (line, column) = (self.line, self.column);
+        self.bump();
+
+        let mut iri = String::new();
+        loop {
+            match self.bump() {
+                Some('>') if !iri.is_empty() => return Ok(iri),
+                Some(c) if c != '\n' && c != '>' => iri.push(c),
+                _ => return Err(error_at("unterminated or empty `<...>`", line, column)),
+            }
+        }
+    }
+
+    fn literal(&mut self) -> Result<(api::Value, String), DgraphError> {
+        let text = self.quoted()?;
+
+        if self.eat('@') {
+            let mut lang = String::new();
+            while let Some(c) = self.peek() {
+                if !(c.is_alphanumeric() || c == '-' || c == ':') {
+                    break;
+                }
+                lang.push(c);
+                self.bump();
+            }
+
+            if lang.is_empty() {
+                return Err(self.error("expected a language tag"));
+            }
+            return Ok((default_value(text), lang));
+        }
+
+        if !self.eat('^') {
+            return Ok((default_value(text), String::new()));
+        }
+        if !self.eat('^') || self.peek() != Some('<') {
+            return Err(self.error("expected `^^<type>`"));
+        }
+
+        let (line, column) = (self.line, self.column);
+        let kind = self.iri()?;
+        let kind = kind.replace("http://www.w3.org/2001/XMLSchema#", "xs:");
+
+        let value = match kind.as_str() {
+            "xs:string" => Some(api::Value::string(text.as_str())),
+            "xs:int" | "xs:integer" | "xs:positiveInteger" => {
+                text.trim().parse().ok().map(api::Value::int)
+            }
+            "xs:float" | "xs:double" => text.trim().parse().ok().map(api::Value::float),
+            "xs:boolean" => parse_bool(text.trim()).map(api::Value::bool),
+            "xs:date" | "xs:dateTime" => {
+                datetime::from_rfc3339(text.trim()).map(api::Value::datetime)
+            }
+            "xs:password" => Some(api::Value::password(text.as_str())),
+            "geo:geojson" => serde_json::from_str(&text)
+                .ok()
+                .and_then(|geojson| geo::from_geojson(&geojson))
+                .map(|wkb| api::Value {
+                    val: Some(api::Value_oneof_val::geo_val(wkb)),
+                    ..Default::default()
+                }),
+            _ => return Err(error_at(&format!("unknown type `{}`", kind), line, column)),
+        };
+
+        value
+            .map(|value| (value, String::new()))
+            .ok_or_else(|| error_at(&format!("invalid {} value `{}`", kind, text), line, column))
+    }
+
+    /// A double-quoted string with `\"`, `\\`, `\n`, `\r`, `\t` and `\uXXXX` escapes.
+    fn quoted(&mut self) -> Result<String, DgraphError> {
+        let (line, column) = (self.line, self.column);
+        self.bump();
+
+        let mut text = String::new();
+        loop {
+            match self.bump() {
+                Some('"') => return Ok(text),
+                Some('\\') => {
+                    let (line, column) = (self.line, self.column);
+                    let escaped = match self.bump() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some(kind @ 'u') | Some(kind @ 'U') => {
+                            let len = if kind == 'u' { 4 } else { 8 };
+                            let hex: String = (0..len).filter_map(|_| self.bump()).collect();
+                            match u32::from_str_radix(&hex, 16)
+                                .ok()
+                                .and_then(std::char::from_u32)
+                            {
+                                Some(c) if hex.len() == len => c,
+                                _ => return Err(error_at("invalid unicode escape", line, column)),
+                            }
+                        }
+                        _ => return Err(error_at("invalid escape", line, column)),
+                    };
+                    text.push(escaped);
+                }
+                Some(c) => text.push(c),
+                None => return Err(error_at("unterminated string", line, column)),
+            }
+        }
+    }
+
+    /// Facets after the opening parenthesis, as `key=value` pairs separated by commas.
+    fn facets(&mut self) -> Result<Vec<api::Facet>, DgraphError> {
+        let mut facets = Vec::new();
+
+        loop {
+            self.skip_space();
+            if self.eat(')') {
+                return Ok(facets);
+            }
+            if !facets.is_empty() {
+                if !self.eat(',') {
+                    return Err(self.error("expected `,` or `)` between facets"));
+                }
+                self.skip_space();
+            }
+
+            let mut key = String::new();
+            while let Some(c) = self.peek() {
+                if c.is_whitespace() || "=,()".contains(c) {
+                    break;
+                }
+                key.push(c);
+                self.bump();
+            }
+            if key.is_empty() {
+                return Err(self.error("expected a facet key"));
+            }
+
+            self.skip_space();
+            if !self.eat('=') {
+                return Err(self.error("expected `=` after the facet key"));
+            }
+            self.skip_space();
+
+            let value = self.facet_value()?;
+            facets.push(api::Facet::with_value(key, value));
+        }
+    }
+
+    /// A quoted string, or an unquoted int, float, bool or datetime, tried in that order as
+    /// Dgraph does.
+    fn facet_value(&mut self) -> Result<FacetValue, DgraphError> {
+        if self.peek() == Some('"') {
+            return self.quoted().map(FacetValue::String);
+        }
+
+        let (line, column) = (self.line, self.column);
+        let mut text = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() || ",()".contains(c) {
+                break;
+            }
+            text.push(c);
+            self.bump();
+        }
+
+        let value = if let Ok(int) = text.parse::<i64>() {
+            FacetValue::Int(int)
+        } else if let Ok(float) = text.parse::<f64>() {
+            FacetValue::Float(float)
+        } else if text == "true" || text == "false" {
+            FacetValue::Bool(text == "true")
+        } else if let Some(time) = datetime::from_rfc3339(&text) {
+            FacetValue::DateTime(time)
+        } else {
+            return Err(error_at(
+                &format!("invalid facet value `{}`", text),
+                line,
+                column,
+            ));
+        };
+
+        Ok(value)
+    }
+
+    /// Skips whitespace and comments, returning whether anything is left.
+    fn skip_space(&mut self) -> bool {
+        while let Some(c) = self.peek() {
+            if c == '#' {
+                while self.peek().map_or(false, |c| c != '\n') {
+                    self.bump();
+                }
+            } else if c.is_whitespace() {
+                self.bump();
+            } else {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).cloned()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn error(&self, message: &str) -> DgraphError {
+        error_at(message, self.line, self.column)
+    }
+}
+
+fn default_value(text: String) -> api::Value {
+    api::Value {
+        val: Some(api::Value_oneof_val::default_val(text)),
+        ..Default::default()
+    }
+}
+
+/// Accepts the spellings Go's `strconv.ParseBool`, and so Dgraph, accepts.
+fn parse_bool(text: &str) -> Option<bool> {
+    match text {
+        "1" | "t" | "T" | "true" | "TRUE" | "True" => Some(true),
+        "0" | "f" | "F" | "false" | "FALSE" | "False" => Some(false),
+        _ => None,
+    }
+}
+
+fn error_at(message: &str, line: usize, column: usize) -> DgraphError {
+    DgraphError::RdfSyntax {
+        message: message.to_string(),
+        line,
+        column,
+    }
+}
@@ -0,0 +1,85 @@
+use serde::de::{Deserialize, DeserializeOwned, Error as _};
+use std::collections::HashMap;
+
+use crate::errors::DgraphError;
+use crate::protos::api;
+use crate::uid::Uid;
+
+/// Longest stretch of raw JSON kept in a `DgraphError::Deserialize`.
+const SNIPPET_LEN: usize = 256;
+
+impl api::Response {
+    /// Deserializes the whole JSON response, one field per query block.
+    pub fn json_as<T: DeserializeOwned>(&self) -> Result<T, DgraphError> {
+        serde_json::from_slice(&self.json).map_err(|error| DgraphError::Deserialize {
+            json: snippet(&self.json, &error),
+            error,
+        })
+    }
+
+    /// Deserializes a single named query block, such as `me` in `{ me(func: ...) { ... } }`,
+    /// without a wrapper struct for the whole response.
+    pub fn block_as<T: DeserializeOwned>(&self, block: &str) -> Result<T, DgraphError> {
+        let mut blocks: HashMap<String, serde_json::Value> = self.json_as()?;
+
+        let value = blocks
+            .remove(block)
+            .ok_or_else(|| DgraphError::Deserialize {
+                error: serde_json::Error::custom(format!("missing query block `{}`", block)),
+                json: truncate(&String::from_utf8_lossy(&self.json)),
+            })?;
+
+        T::deserialize(&value).map_err(|error| DgraphError::Deserialize {
+            json: truncate(&value.to_string()),
+            error,
+        })
+    }
+}
+
+/// The outcome of a mutation: the UIDs assigned to its blank nodes and the context of the
+/// transaction it ran in.
+#[derive(Clone, Debug, Default)]
+pub struct Assigned {
+    /// Maps blank node names, without their `_:` prefix, to the UIDs created for them.
+    pub uids: HashMap<String, Uid>,
+    pub context: api::TxnContext,
+}
+
+impl Assigned {
+    pub fn from_response(response: &api::Response) -> Result<Assigned, DgraphError> {
+        let uids = response
+            .uids
+            .iter()
+            .map(|(name, uid)| Ok((name.clone(), uid.parse::<Uid>()?)))
+            .collect::<Result<_, DgraphError>>()?;
+
+        Ok(Assigned {
+            uids,
+            context: response.get_txn().clone(),
+        })
+    }
+
+    /// Returns the UID assigned to the blank node `name`, given with or without `_:`.
+    pub fn uid(&self, name: &str) -> Option<Uid> {
+        let name = name.trim_start_matches("_:");
+        self.uids.get(name).cloned()
+    }
+}
+
+/// Extracts the part of `json` around the position `error` points at.
+fn snippet(json: &[u8], error: &serde_json::Error) -> String {
+    let json = String::from_utf8_lossy(json);
+
+    match json.lines().nth(error.line().saturating_sub(1)) {
+        Some(line) if error.line() > 0 && line.chars().count() > SNIPPET_LEN => {
+            let start = error.column().saturating_sub(SNIPPET_LEN / 2);
+            line.chars().skip(start).take(SNIPPET_LEN).collect()
+        }
+        Some(line) if error.line() > 0 => line.to_string(),
+        _ => truncate(&json),
+    }
+}
+
+pub(crate) fn truncate(json: &str) -> String {
+    json.chars().take(SNIPPET_LEN).collect()
+}
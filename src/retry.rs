@@ -0,0 +1,61 @@
+use rand::prelude::*;
+use std::time::Duration;
+
+/// Controls how `Dgraph::run_in_txn_with` retries transactions that were aborted
+/// because of a conflict with a concurrent transaction.
+///
+/// Delays grow exponentially from `base_delay` up to `max_delay`, and each one is
+/// picked at random below that bound ("full jitter") so that conflicting clients
+/// do not retry in lockstep.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one.
+    pub max_attempts: usize,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(10),
+            max_delay: Duration::from_secs(1),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new(max_attempts: usize) -> Self {
+        RetryPolicy {
+            max_attempts,
+            ..Default::default()
+        }
+    }
+
+    pub fn base_delay(mut self, delay: Duration) -> Self {
+        self.base_delay = delay;
+        self
+    }
+
+    pub fn max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    /// Returns the delay to wait after the given failed attempt, counting from 1.
+    pub fn backoff(&self, attempt: usize) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(31) as u32;
+        let ceiling = self
+            .base_delay
+            .checked_mul(1 << exponent)
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay));
+        let ceiling_nanos = ceiling.as_nanos() as u64;
+
+        if ceiling_nanos == 0 {
+            return Duration::from_nanos(0);
+        }
+
+        Duration::from_nanos(thread_rng().gen_range(0, ceiling_nanos + 1))
+    }
+}
@@ -0,0 +1,904 @@
+//! A typed model of the Dgraph schema language.
+//!
+//! A `Schema` renders the text expected by `Operation.schema` and can be parsed back from it.
+//!
+//! ```
+//! use dgraph::schema::{Predicate, ScalarType, Schema, Tokenizer, TypeDef};
+//!
+//! let schema = Schema::new()
+//!     .predicate(Predicate::new("name", ScalarType::String).index(vec![Tokenizer::Exact]))
+//!     .predicate(Predicate::new("friend", ScalarType::Uid).list().reverse().count())
+//!     .type_def(TypeDef::new("Person").field("name").field("friend"));
+//!
+//! let text = schema.to_string();
+//! assert_eq!(
+//!     text,
+//!     "name: string @index(exact) .\nfriend: [uid] @reverse @count .\n\ntype Person {\n  name\n  friend\n}\n"
+//! );
+//! assert_eq!(text.parse::<Schema>().unwrap(), schema);
+//! ```
+
+use serde::de::Error as _;
+use serde_derive::Deserialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::errors::DgraphError;
+use crate::protos::api;
+use crate::response::truncate;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ScalarType {
+    Default,
+    Int,
+    Float,
+    String,
+    Bool,
+    DateTime,
+    Geo,
+    Password,
+    Uid,
+}
+
+impl ScalarType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ScalarType::Default => "default",
+            ScalarType::Int => "int",
+            ScalarType::Float => "float",
+            ScalarType::String => "string",
+            ScalarType::Bool => "bool",
+            ScalarType::DateTime => "datetime",
+            ScalarType::Geo => "geo",
+            ScalarType::Password => "password",
+            ScalarType::Uid => "uid",
+        }
+    }
+}
+
+impl fmt::Display for ScalarType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+fn scalar_type(name: &str) -> Option<ScalarType> {
+    Some(match name {
+        "default" => ScalarType::Default,
+        "int" => ScalarType::Int,
+        "float" => ScalarType::Float,
+        "string" => ScalarType::String,
+        "bool" => ScalarType::Bool,
+        "datetime" => ScalarType::DateTime,
+        "geo" => ScalarType::Geo,
+        "password" => ScalarType::Password,
+        "uid" => ScalarType::Uid,
+        _ => return None,
+    })
+}
+
+/// A tokenizer used by `@index`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Tokenizer {
+    Exact,
+    Hash,
+    Term,
+    Fulltext,
+    Trigram,
+    Int,
+    Float,
+    Bool,
+    Geo,
+    Year,
+    Month,
+    Day,
+    Hour,
+    /// A tokenizer provided by a plugin.
+    Custom(String),
+}
+
+impl Tokenizer {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Tokenizer::Exact => "exact",
+            Tokenizer::Hash => "hash",
+            Tokenizer::Term => "term",
+            Tokenizer::Fulltext => "fulltext",
+            Tokenizer::Trigram => "trigram",
+            Tokenizer::Int => "int",
+            Tokenizer::Float => "float",
+            Tokenizer::Bool => "bool",
+            Tokenizer::Geo => "geo",
+            Tokenizer::Year => "year",
+            Tokenizer::Month => "month",
+            Tokenizer::Day => "day",
+            Tokenizer::Hour => "hour",
+            Tokenizer::Custom(name) => name,
+        }
+    }
+}
+
+impl fmt::Display for Tokenizer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<&str> for Tokenizer {
+    fn from(name: &str) -> Tokenizer {
+        match name {
+            "exact" => Tokenizer::Exact,
+            "hash" => Tokenizer::Hash,
+            "term" => Tokenizer::Term,
+            "fulltext" => Tokenizer::Fulltext,
+            "trigram" => Tokenizer::Trigram,
+            "int" => Tokenizer::Int,
+            "float" => Tokenizer::Float,
+            "bool" => Tokenizer::Bool,
+            "geo" => Tokenizer::Geo,
+            "year" => Tokenizer::Year,
+            "month" => Tokenizer::Month,
+            "day" => Tokenizer::Day,
+            "hour" => Tokenizer::Hour,
+            name => Tokenizer::Custom(name.to_string()),
+        }
+    }
+}
+
+/// The tokenizers of an `@index` directive.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Index {
+    pub tokenizers: Vec<Tokenizer>,
+}
+
+impl Index {
+    pub fn new(tokenizers: Vec<Tokenizer>) -> Index {
+        Index { tokenizers }
+    }
+}
+
+impl fmt::Display for Index {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tokenizers: Vec<&str> = self.tokenizers.iter().map(Tokenizer::as_str).collect();
+        write!(f, "@index({})", tokenizers.join(", "))
+    }
+}
+
+/// The definition of a single predicate, such as `name: [string] @index(exact) @lang .`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Predicate {
+    pub name: String,
+    pub scalar: ScalarType,
+    pub list: bool,
+    pub index: Option<Index>,
+    pub reverse: bool,
+    pub count: bool,
+    pub upsert: bool,
+    pub lang: bool,
+    pub no_conflict: bool,
+}
+
+impl Predicate {
+    pub fn new(name: impl Into<String>, scalar: ScalarType) -> Predicate {
+        Predicate {
+            name: name.into(),
+            scalar,
+            list: false,
+            index: None,
+            reverse: false,
+            count: false,
+            upsert: false,
+            lang: false,
+            no_conflict: false,
+        }
+    }
+
+    /// Makes the predicate hold a list of values, as in `[string]`.
+    pub fn list(mut self) -> Self {
+        self.list = true;
+        self
+    }
+
+    pub fn index(mut self, tokenizers: Vec<Tokenizer>) -> Self {
+        self.index = Some(Index::new(tokenizers));
+        self
+    }
+
+    pub fn reverse(mut self) -> Self {
+        self.reverse = true;
+        self
+    }
+
+    pub fn count(mut self) -> Self {
+        self.count = true;
+        self
+    }
+
+    pub fn upsert(mut self) -> Self {
+        self.upsert = true;
+        self
+    }
+
+    pub fn lang(mut self) -> Self {
+        self.lang = true;
+        self
+    }
+
+    /// Stops Dgraph from detecting conflicts on the predicate, as in `@noconflict`.
+    pub fn no_conflict(mut self) -> Self {
+        self.no_conflict = true;
+        self
+    }
+}
+
+impl fmt::Display for Predicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", name(&self.name))?;
+
+        if self.list {
+            write!(f, "[{}]", self.scalar)?;
+        } else {
+            write!(f, "{}", self.scalar)?;
+        }
+
+        if let Some(index) = &self.index {
+            write!(f, " {}", index)?;
+        }
+        if self.reverse {
+            f.write_str(" @reverse")?;
+        }
+        if self.count {
+            f.write_str(" @count")?;
+        }
+        if self.upsert {
+            f.write_str(" @upsert")?;
+        }
+        if self.lang {
+            f.write_str(" @lang")?;
+        }
+        if self.no_conflict {
+            f.write_str(" @noconflict")?;
+        }
+
+        f.write_str(" .")
+    }
+}
+
+/// A type definition listing the predicates of a `dgraph.type`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypeDef {
+    pub name: String,
+    pub fields: Vec<String>,
+}
+
+impl TypeDef {
+    pub fn new(name: impl Into<String>) -> TypeDef {
+        TypeDef {
+            name: name.into(),
+            fields: Vec::new(),
+        }
+    }
+
+    /// Adds a predicate to the type. Reverse edges are named `~predicate`.
+    pub fn field(mut self, predicate: impl Into<String>) -> Self {
+        self.fields.push(predicate.into());
+        self
+    }
+}
+
+impl fmt::Display for TypeDef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "type {} {{", name(&self.name))?;
+        for field in &self.fields {
+            writeln!(f, "  {}", name(field))?;
+        }
+        f.write_str("}")
+    }
+}
+
+/// A whole schema: predicate definitions followed by type definitions.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Schema {
+    pub predicates: Vec<Predicate>,
+    pub types: Vec<TypeDef>,
+}
+
+impl Schema {
+    pub fn new() -> Schema {
+        Default::default()
+    }
+
+    pub fn predicate(mut self, predicate: Predicate) -> Self {
+        self.predicates.push(predicate);
+        self
+    }
+
+    pub fn type_def(mut self, type_def: TypeDef) -> Self {
+        self.types.push(type_def);
+        self
+    }
+
+    pub fn get_predicate(&self, name: &str) -> Option<&Predicate> {
+        self.predicates
+            .iter()
+            .find(|predicate| predicate.name == name)
+    }
+
+    pub fn get_type(&self, name: &str) -> Option<&TypeDef> {
+        self.types.iter().find(|type_def| type_def.name == name)
+    }
+
+    /// An `alter` operation applying the schema.
+    pub fn to_operation(&self) -> api::Operation {
+        api::Operation {
+            schema: self.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+impl fmt::Display for Schema {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for predicate in &self.predicates {
+            writeln!(f, "{}", predicate)?;
+        }
+        for type_def in &self.types {
+            writeln!(f)?;
+            writeln!(f, "{}", type_def)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Schema {
+    type Err = DgraphError;
+
+    /// Parses schema text. Types may also list their fields in the older `name: type` form.
+    fn from_str(text: &str) -> Result<Schema, DgraphError> {
+        Parser::new(text).schema()
+    }
+}
+
+impl Schema {
+    /// Reads the result of a `schema {}` or `schema(type: [...])` query, leaving out Dgraph's
+    /// internal `dgraph.*` predicates and types.
+    pub(crate) fn from_response(response: &api::Response) -> Result<Schema, DgraphError> {
+        let json: SchemaJson = response.json_as()?;
+        let mut schema = Schema::new();
+
+        for node in json.schema {
+            if node.predicate.starts_with("dgraph.") {
+                continue;
+            }
+
+            let scalar = scalar_type(&node.scalar).ok_or_else(|| DgraphError::Deserialize {
+                error: serde_json::Error::custom(format!(
+                    "unknown type `{}` of predicate `{}`",
+                    node.scalar, node.predicate
+                )),
+                json: truncate(&String::from_utf8_lossy(&response.json)),
+            })?;
+
+            let mut predicate = Predicate::new(node.predicate, scalar);
+            predicate.list = node.list;
+            predicate.reverse = node.reverse;
+            predicate.count = node.count;
+            predicate.upsert = node.upsert;
+            predicate.lang = node.lang;
+            predicate.no_conflict = node.no_conflict;
+            if node.index {
+                let tokenizers = node.tokenizer.iter().map(|name| name.as_str().into());
+                predicate.index = Some(Index::new(tokenizers.collect()));
+            }
+
+            schema.predicates.push(predicate);
+        }
+
+        for node in json.types {
+            if node.name.starts_with("dgraph.") {
+                continue;
+            }
+
+            let fields = node.fields.into_iter().map(|field| {
+                field
+                    .name
+                    .trim_matches(|c| c == '<' || c == '>')
+                    .to_string()
+            });
+
+            schema.types.push(TypeDef {
+                name: node.name,
+                fields: fields.collect(),
+            });
+        }
+
+        Ok(schema)
+    }
+}
+
+#[derive(Deserialize)]
+struct SchemaJson {
+    #[serde(default)]
+    schema: Vec<PredicateJson>,
+    #[serde(default)]
+    types: Vec<TypeJson>,
+}
+
+#[derive(Deserialize)]
+struct PredicateJson {
+    predicate: String,
+    #[serde(rename = "type")]
+    scalar: String,
+    #[serde(default)]
+    index: bool,
+    #[serde(default)]
+    tokenizer: Vec<String>,
+    #[serde(default)]
+    reverse: bool,
+    #[serde(default)]
+    count: bool,
+    #[serde(default)]
+    list: bool,
+    #[serde(default)]
+    upsert: bool,
+    #[serde(default)]
+    lang: bool,
+    #[serde(default)]
+    no_conflict: bool,
+}
+
+#[derive(Deserialize)]
+struct TypeJson {
+    name: String,
+    #[serde(default)]
+    fields: Vec<FieldJson>,
+}
+
+#[derive(Deserialize)]
+struct FieldJson {
+    name: String,
+}
+
+/// A predicate whose definition differs between two schemas.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PredicateChange {
+    pub from: Predicate,
+    pub to: Predicate,
+}
+
+impl PredicateChange {
+    pub fn added_tokenizers(&self) -> Vec<Tokenizer> {
+        tokenizers(&self.to)
+            .difference(&tokenizers(&self.from))
+            .cloned()
+            .collect()
+    }
+
+    pub fn removed_tokenizers(&self) -> Vec<Tokenizer> {
+        tokenizers(&self.from)
+            .difference(&tokenizers(&self.to))
+            .cloned()
+            .collect()
+    }
+}
+
+/// A type whose fields differ between two schemas.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypeChange {
+    pub from: TypeDef,
+    pub to: TypeDef,
+}
+
+impl TypeChange {
+    pub fn added_fields(&self) -> Vec<String> {
+        missing_from(&self.to.fields, &self.from.fields)
+    }
+
+    pub fn removed_fields(&self) -> Vec<String> {
+        missing_from(&self.from.fields, &self.to.fields)
+    }
+}
+
+/// The differences between a current and a desired schema, as computed by `diff`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SchemaDiff {
+    pub added_predicates: Vec<Predicate>,
+    pub removed_predicates: Vec<Predicate>,
+    pub changed_predicates: Vec<PredicateChange>,
+    pub added_types: Vec<TypeDef>,
+    pub removed_types: Vec<TypeDef>,
+    pub changed_types: Vec<TypeChange>,
+}
+
+impl SchemaDiff {
+    pub fn is_empty(&self) -> bool {
+        *self == SchemaDiff::default()
+    }
+
+    /// The operations that turn the current schema into the desired one, to be passed to
+    /// `Dgraph::alter` in order: one schema update for the added and changed predicates and
+    /// types, then, only if `prune` is set, a `DropOp::TYPE` per removed type and a
+    /// `DropOp::ATTR` per removed predicate.
+    ///
+    /// Dropping a predicate deletes all of its data, so inspect `removed_predicates` before
+    /// pruning. Without it, removed predicates and types are only reported and stay on the
+    /// server.
+    pub fn to_operations(&self, prune: bool) -> Vec<api::Operation> {
+        let update = Schema {
+            predicates: self
+                .added_predicates
+                .iter()
+                .cloned()
+                .chain(
+                    self.changed_predicates
+                        .iter()
+                        .map(|change| change.to.clone()),
+                )
+                .collect(),
+            types: self
+                .added_types
+                .iter()
+                .cloned()
+                .chain(self.changed_types.iter().map(|change| change.to.clone()))
+                .collect(),
+        };
+
+        let mut operations = Vec::new();
+        if update != Schema::default() {
+            operations.push(update.to_operation());
+        }
+        if !prune {
+            return operations;
+        }
+
+        for type_def in &self.removed_types {
+            operations.push(api::Operation {
+                drop_op: api::Operation_DropOp::TYPE,
+                drop_value: type_def.name.clone(),
+                ..Default::default()
+            });
+        }
+
+        for predicate in &self.removed_predicates {
+            operations.push(api::Operation {
+                drop_op: api::Operation_DropOp::ATTR,
+                drop_value: predicate.name.clone(),
+                ..Default::default()
+            });
+        }
+
+        operations
+    }
+}
+
+/// Compares two schemas. Tokenizers and type fields are compared regardless of their order.
+pub fn diff(current: &Schema, desired: &Schema) -> SchemaDiff {
+    let mut diff = SchemaDiff::default();
+
+    for predicate in &desired.predicates {
+        match current.get_predicate(&predicate.name) {
+            None => diff.added_predicates.push(predicate.clone()),
+            Some(from) if !same_predicate(from, predicate) => {
+                diff.changed_predicates.push(PredicateChange {
+                    from: from.clone(),
+                    to: predicate.clone(),
+                })
+            }
+            Some(_) => (),
+        }
+    }
+
+    for predicate in &current.predicates {
+        if desired.get_predicate(&predicate.name).is_none() {
+            diff.removed_predicates.push(predicate.clone());
+        }
+    }
+
+    for type_def in &desired.types {
+        match current.get_type(&type_def.name) {
+            None => diff.added_types.push(type_def.clone()),
+            Some(from) if !same_fields(&from.fields, &type_def.fields) => {
+                diff.changed_types.push(TypeChange {
+                    from: from.clone(),
+                    to: type_def.clone(),
+                })
+            }
+            Some(_) => (),
+        }
+    }
+
+    for type_def in &current.types {
+        if desired.get_type(&type_def.name).is_none() {
+            diff.removed_types.push(type_def.clone());
+        }
+    }
+
+    diff
+}
+
+fn tokenizers(predicate: &Predicate) -> HashSet<Tokenizer> {
+    match &predicate.index {
+        Some(index) => index.tokenizers.iter().cloned().collect(),
+        None => HashSet::new(),
+    }
+}
+
+fn same_predicate(a: &Predicate, b: &Predicate) -> bool {
+    a.scalar == b.scalar
+        && a.list == b.list
+        && a.reverse == b.reverse
+        && a.count == b.count
+        && a.upsert == b.upsert
+        && a.lang == b.lang
+        && a.no_conflict == b.no_conflict
+        && tokenizers(a) == tokenizers(b)
+}
+
+fn same_fields(a: &[String], b: &[String]) -> bool {
+    missing_from(a, b).is_empty() && missing_from(b, a).is_empty()
+}
+
+/// The entries of `fields` that are not in `other`.
+fn missing_from(fields: &[String], other: &[String]) -> Vec<String> {
+    fields
+        .iter()
+        .filter(|field| !other.contains(field))
+        .cloned()
+        .collect()
+}
+
+/// Writes a predicate or type name, in angle brackets when it is not a plain identifier.
+fn name(name: &str) -> String {
+    let plain = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '.');
+
+    if plain {
+        name.to_string()
+    } else {
+        format!("<{}>", name)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Name(String),
+    Punct(char),
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize, usize)>,
+    position: usize,
+    end: (usize, usize),
+}
+
+impl Parser {
+    fn new(text: &str) -> Parser {
+        let mut tokens = Vec::new();
+        let mut chars = text.chars().peekable();
+        let (mut line, mut column) = (1, 1);
+
+        while let Some(c) = chars.next() {
+            let start = (line, column);
+            if c == '\n' {
+                line += 1;
+                column = 1;
+                continue;
+            }
+            column += 1;
+
+            match c {
+                '#' => {
+                    while let Some(&c) = chars.peek() {
+                        if c == '\n' {
+                            break;
+                        }
+                        chars.next();
+                    }
+                }
+                '<' => {
+                    let mut name = String::new();
+                    while let Some(c) = chars.next() {
+                        column += 1;
+                        if c == '>' {
+                            break;
+                        }
+                        name.push(c);
+                    }
+                    tokens.push((Token::Name(name), start.0, start.1));
+                }
+                c if c.is_whitespace() => (),
+                c if c != '.' && is_name_char(c) => {
+                    let mut name = c.to_string();
+                    while let Some(&c) = chars.peek() {
+                        if !is_name_char(c) {
+                            break;
+                        }
+                        name.push(c);
+                        chars.next();
+                        column += 1;
+                    }
+
+                    // A statement may end right after a name, as in `name: string.`.
+                    let terminated = name.ends_with('.');
+                    if terminated {
+                        name.pop();
+                    }
+                    tokens.push((Token::Name(name), start.0, start.1));
+                    if terminated {
+                        tokens.push((Token::Punct('.'), line, column - 1));
+                    }
+                }
+                c => tokens.push((Token::Punct(c), start.0, start.1)),
+            }
+        }
+
+        Parser {
+            tokens,
+            position: 0,
+            end: (line, column),
+        }
+    }
+
+    fn schema(mut self) -> Result<Schema, DgraphError> {
+        let mut schema = Schema::new();
+
+        while self.peek(0).is_some() {
+            let type_def = match (self.peek(0), self.peek(1)) {
+                (Some(Token::Name(keyword)), Some(Token::Name(_))) => keyword == "type",
+                _ => false,
+            };
+
+            if type_def {
+                self.position += 1;
+                schema.types.push(self.type_def()?);
+            } else {
+                schema.predicates.push(self.predicate()?);
+            }
+        }
+
+        Ok(schema)
+    }
+
+    fn predicate(&mut self) -> Result<Predicate, DgraphError> {
+        let name = self.name()?;
+        self.expect(':')?;
+
+        let list = self.eat('[');
+        let (line, column) = self.location();
+        let scalar_name = self.name()?;
+        let scalar = match scalar_type(&scalar_name) {
+            Some(scalar) => scalar,
+            None => {
+                return Err(error_at(
+                    &format!("unknown type `{}`", scalar_name),
+                    line,
+                    column,
+                ))
+            }
+        };
+        if list {
+            self.expect(']')?;
+        }
+
+        let mut predicate = Predicate::new(name, scalar);
+        predicate.list = list;
+
+        while !self.eat('.') {
+            self.expect('@')?;
+            let (line, column) = self.location();
+
+            match self.name()?.as_str() {
+                "index" => {
+                    self.expect('(')?;
+                    let mut tokenizers = Vec::new();
+                    while !self.eat(')') {
+                        if !tokenizers.is_empty() {
+                            self.expect(',')?;
+                        }
+                        tokenizers.push(Tokenizer::from(self.name()?.as_str()));
+                    }
+                    predicate.index = Some(Index::new(tokenizers));
+                }
+                "reverse" => predicate.reverse = true,
+                "count" => predicate.count = true,
+                "upsert" => predicate.upsert = true,
+                "lang" => predicate.lang = true,
+                "noconflict" => predicate.no_conflict = true,
+                directive => {
+                    return Err(error_at(
+                        &format!("unknown directive `@{}`", directive),
+                        line,
+                        column,
+                    ))
+                }
+            }
+        }
+
+        Ok(predicate)
+    }
+
+    fn type_def(&mut self) -> Result<TypeDef, DgraphError> {
+        let mut type_def = TypeDef::new(self.name()?);
+        self.expect('{')?;
+
+        while !self.eat('}') {
+            let field = self.name()?;
+            if self.eat(':') {
+                self.eat('[');
+                self.name()?;
+                self.eat(']');
+                self.eat('!');
+            }
+            self.eat(',');
+            type_def.fields.push(field);
+        }
+
+        Ok(type_def)
+    }
+
+    fn peek(&self, offset: usize) -> Option<&Token> {
+        self.tokens
+            .get(self.position + offset)
+            .map(|(token, _, _)| token)
+    }
+
+    fn location(&self) -> (usize, usize) {
+        match self.tokens.get(self.position) {
+            Some((_, line, column)) => (*line, *column),
+            None => self.end,
+        }
+    }
+
+    fn eat(&mut self, punct: char) -> bool {
+        if self.peek(0) == Some(&Token::Punct(punct)) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, punct: char) -> Result<(), DgraphError> {
+        if self.eat(punct) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{}`", punct)))
+        }
+    }
+
+    fn name(&mut self) -> Result<String, DgraphError> {
+        match self.peek(0).cloned() {
+            Some(Token::Name(name)) => {
+                self.position += 1;
+                Ok(name)
+            }
+            _ => Err(self.error("expected a name")),
+        }
+    }
+
+    fn error(&self, message: &str) -> DgraphError {
+        let (line, column) = self.location();
+        let found = match self.peek(0) {
+            Some(Token::Name(name)) => format!("`{}`", name),
+            Some(Token::Punct(punct)) => format!("`{}`", punct),
+            None => "end of schema".to_string(),
+        };
+
+        error_at(&format!("{}, found {}", message, found), line, column)
+    }
+}
+
+fn error_at(message: &str, line: usize, column: usize) -> DgraphError {
+    DgraphError::SchemaSyntax {
+        message: message.to_string(),
+        line,
+        column,
+    }
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '.' || c == '~'
+}
@@ -1,28 +1,48 @@
-use std::collections::HashMap;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::time::Duration;
 
+use crate::balancer::Endpoint;
 use crate::errors::DgraphError;
+use crate::pages::Pages;
 use crate::protos::api;
-use crate::protos::api_grpc;
+use crate::response::Assigned;
+use crate::upsert::{Upsert, UpsertResponse};
+use crate::vars::Vars;
 
-pub struct Txn<'a> {
+pub struct Txn {
     pub(super) context: api::TxnContext,
     pub(super) finished: bool,
     pub(super) read_only: bool,
     pub(super) best_effort: bool,
     pub(super) mutated: bool,
-    pub(super) client: &'a api_grpc::DgraphClient,
-    pub(super) dgraph: &'a crate::Dgraph,
+    /// Whether a request committing the transaction was sent, so that a failure may
+    /// have been applied anyway.
+    pub(super) commit_sent: bool,
+    pub(super) timeout: Option<Duration>,
+    pub(super) endpoint_index: usize,
+    pub(super) dgraph: crate::Dgraph,
 }
 
 /// Call Txn::discard() once txn goes out of scope.
 /// This is safe to do so, and is possible a no-op
-impl Drop for Txn<'_> {
+impl Drop for Txn {
     fn drop(&mut self) {
         let _ = self.discard();
     }
 }
 
-impl Txn<'_> {
+impl Txn {
+    /// Overrides the client's default deadline for every request made by this
+    /// transaction, including its commit or discard. Calls exceeding it fail with
+    /// `DgraphError::DeadlineExceeded`.
+    ///
+    /// Async calls can additionally be cancelled at any point by dropping their future.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
     /// `best_effort` enables best effort in read-only queries. Using this flag
     /// will ask the Dgraph Alpha to try to get timestamps from memory in a best
     /// effort to reduce the number of outbound requests to Zero. This may yield
@@ -36,17 +56,23 @@ impl Txn<'_> {
     }
 
     pub fn query(&mut self, query: &str) -> Result<api::Response, DgraphError> {
-        self.query_with_vars(query, HashMap::new())
+        self.query_with_vars(query, Vars::new())
     }
 
+    /// Runs a query with variables, given as `Vars` or as a map of already formatted values.
+    /// Fails with `DgraphError::InvalidVars` before sending anything if a variable is missing
+    /// from the query header or declared with a different type.
     pub fn query_with_vars(
         &mut self,
         query: &str,
-        vars: HashMap<String, String>,
+        vars: impl Into<Vars>,
     ) -> Result<api::Response, DgraphError> {
+        let vars = vars.into();
+        vars.check(query)?;
+
         let mut request = api::Request {
             query: query.to_string(),
-            vars,
+            vars: vars.into(),
             start_ts: self.context.get_start_ts(),
             read_only: self.read_only,
             best_effort: self.best_effort,
@@ -56,6 +82,59 @@ impl Txn<'_> {
         self.do_request(&mut request)
     }
 
+    /// Runs the query and deserializes the JSON response into `T`, which has one field per
+    /// query block. Use `api::Response::block_as` to pick out a single block instead.
+    pub fn query_as<T: DeserializeOwned>(&mut self, query: &str) -> Result<T, DgraphError> {
+        self.query(query)?.json_as()
+    }
+
+    pub fn query_with_vars_as<T: DeserializeOwned>(
+        &mut self,
+        query: &str,
+        vars: impl Into<Vars>,
+    ) -> Result<T, DgraphError> {
+        self.query_with_vars(query, vars)?.json_as()
+    }
+
+    /// Walks the results of a query in pages of `page_size` nodes, each deserialized into `T`.
+    /// The query has a single block that selects `uid` and takes its page from the `$first`
+    /// and `$after` variables, which are set for every page, starting after `0x0`:
+    ///
+    /// ```no_run
+    /// # #[derive(serde_derive::Deserialize)]
+    /// # struct Person { uid: dgraph::Uid, name: String }
+    /// # fn main() -> Result<(), dgraph::DgraphError> {
+    /// # let dgraph = dgraph::make_dgraph!(dgraph::new_dgraph_client("localhost:9080"));
+    /// let query = r#"query people($first: int, $after: string) {
+    ///     people(func: type(Person), first: $first, after: $after) { uid name }
+    /// }"#;
+    ///
+    /// let mut txn = dgraph.new_readonly_txn()?;
+    /// for page in txn.paginate::<Person>(query, 100) {
+    ///     for person in page? {
+    ///         println!("{}", person.name);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// All pages are read at the transaction's `start_ts`. Pages follow each other by UID, so
+    /// the block must not be ordered by anything else.
+    pub fn paginate<T: DeserializeOwned>(&mut self, query: &str, page_size: i64) -> Pages<'_, T> {
+        self.paginate_with_vars(query, Vars::new(), page_size)
+    }
+
+    /// Like `paginate`, with other variables for the query besides `$first` and `$after`.
+    pub fn paginate_with_vars<T: DeserializeOwned>(
+        &mut self,
+        query: &str,
+        vars: impl Into<Vars>,
+        page_size: i64,
+    ) -> Pages<'_, T> {
+        Pages::new(self, query.to_string(), vars.into(), page_size)
+    }
+
     pub fn mutate(&mut self, mu: api::Mutation) -> Result<api::Response, DgraphError> {
         let mut request = api::Request::new();
         let mutations = vec![mu.clone()];
@@ -66,32 +145,74 @@ impl Txn<'_> {
         self.do_request(&mut request)
     }
 
-    pub fn do_request(&mut self, request: &mut api::Request) -> Result<api::Response, DgraphError> {
-        let mutation_list = request.get_mutations();
+    /// Creates or updates the nodes described by `value`, serialized as mutation JSON.
+    pub fn set_json<T: Serialize>(&mut self, value: &T) -> Result<Assigned, DgraphError> {
+        self.mutate_json(value, false, false)
+    }
 
-        if self.finished {
-            return Err(DgraphError::TxnFinished);
-        }
+    /// Deletes the predicates and nodes described by `value`, serialized as mutation JSON.
+    pub fn delete_json<T: Serialize>(&mut self, value: &T) -> Result<Assigned, DgraphError> {
+        self.mutate_json(value, true, false)
+    }
 
-        if !mutation_list.is_empty() {
-            if self.read_only {
-                return Err(DgraphError::TxnReadOnly);
-            }
+    /// Like `set_json`, but commits the transaction along with the mutation.
+    pub fn set_json_commit_now<T: Serialize>(
+        &mut self,
+        value: &T,
+    ) -> Result<Assigned, DgraphError> {
+        self.mutate_json(value, false, true)
+    }
 
-            self.mutated = true;
+    /// Like `delete_json`, but commits the transaction along with the mutation.
+    pub fn delete_json_commit_now<T: Serialize>(
+        &mut self,
+        value: &T,
+    ) -> Result<Assigned, DgraphError> {
+        self.mutate_json(value, true, true)
+    }
+
+    fn mutate_json<T: Serialize>(
+        &mut self,
+        value: &T,
+        delete: bool,
+        commit_now: bool,
+    ) -> Result<Assigned, DgraphError> {
+        let json = serde_json::to_vec(value).map_err(DgraphError::Serialize)?;
+
+        let mut mu = api::Mutation::new();
+        if delete {
+            mu.set_delete_json(json);
+        } else {
+            mu.set_set_json(json);
         }
+        mu.set_commit_now(commit_now);
 
-        request.set_start_ts(self.context.get_start_ts());
+        Assigned::from_response(&self.mutate(mu)?)
+    }
 
-        let response = match self.client.query(&request) {
+    /// Runs an upsert block: its query and all of its conditional mutations in one request.
+    pub fn upsert(&mut self, upsert: Upsert) -> Result<UpsertResponse, DgraphError> {
+        let (mut request, blank_nodes) = upsert.into_request()?;
+        let response = self.do_request(&mut request)?;
+
+        UpsertResponse::new(response, blank_nodes)
+    }
+
+    pub fn do_request(&mut self, request: &mut api::Request) -> Result<api::Response, DgraphError> {
+        self.prepare_request(request)?;
+
+        let response = match self
+            .endpoint()
+            .query(&request, self.dgraph.call_option(self.timeout)?)
+        {
             Ok(response) => response,
             Err(err) => {
                 let retry_result = if self.dgraph.is_jwt_expired(&err) {
-                    match self.dgraph.retry_login() {
-                        Ok(_) => match self.client.query(&request) {
-                            Ok(response) => Ok(response),
-                            Err(err) => Err(err.into()),
-                        },
+                    match self.dgraph.retry_login_with(self.timeout) {
+                        Ok(_) => self
+                            .dgraph
+                            .call_option(self.timeout)
+                            .and_then(|opt| Ok(self.endpoint().query(&request, opt)?)),
                         Err(err) => Err(err),
                     }
                 } else {
@@ -108,11 +229,7 @@ impl Txn<'_> {
             }
         };
 
-        if request.commit_now {
-            self.finished = true;
-        }
-
-        self.merge_context(response.get_txn())?;
+        self.finish_request(request, &response)?;
 
         Ok(response)
     }
@@ -142,14 +259,17 @@ impl Txn<'_> {
             return Ok(());
         }
 
-        let res = self.client.commit_or_abort(&self.context);
+        let res = self
+            .endpoint()
+            .commit_or_abort(&self.context, self.dgraph.call_option(self.timeout)?);
 
         match res {
             Ok(_) => Ok(()),
             Err(err) => {
                 if self.dgraph.is_jwt_expired(&err) {
-                    self.dgraph.retry_login()?;
-                    self.client.commit_or_abort(&self.context)?;
+                    self.dgraph.retry_login_with(self.timeout)?;
+                    self.endpoint()
+                        .commit_or_abort(&self.context, self.dgraph.call_option(self.timeout)?)?;
 
                     Ok(())
                 } else {
@@ -159,6 +279,44 @@ impl Txn<'_> {
         }
     }
 
+    fn endpoint(&self) -> &Endpoint {
+        self.dgraph.endpoint_at(self.endpoint_index)
+    }
+
+    fn prepare_request(&mut self, request: &mut api::Request) -> Result<(), DgraphError> {
+        if self.finished {
+            return Err(DgraphError::TxnFinished);
+        }
+
+        if !request.get_mutations().is_empty() {
+            if self.read_only {
+                return Err(DgraphError::TxnReadOnly);
+            }
+
+            self.mutated = true;
+        }
+
+        if request.commit_now {
+            self.commit_sent = true;
+        }
+
+        request.set_start_ts(self.context.get_start_ts());
+
+        Ok(())
+    }
+
+    fn finish_request(
+        &mut self,
+        request: &api::Request,
+        response: &api::Response,
+    ) -> Result<(), DgraphError> {
+        if request.commit_now {
+            self.finished = true;
+        }
+
+        self.merge_context(response.get_txn())
+    }
+
     fn merge_context(&mut self, src: &api::TxnContext) -> Result<(), DgraphError> {
         if self.context.start_ts == 0 {
             self.context.start_ts = src.start_ts;
@@ -179,3 +337,139 @@ impl Txn<'_> {
         Ok(())
     }
 }
+
+/// Non-blocking counterparts of the transaction methods, built on the generated `*_async`
+/// gRPC calls. They share the JWT retry, read-only/best-effort checks, context merging and
+/// discard-on-error semantics of their blocking versions.
+///
+/// `Drop` still discards synchronously, so prefer calling `discard_async` explicitly when an
+/// uncommitted transaction is abandoned inside an async task.
+impl Txn {
+    pub async fn query_async(&mut self, query: &str) -> Result<api::Response, DgraphError> {
+        self.query_with_vars_async(query, Vars::new()).await
+    }
+
+    pub async fn query_with_vars_async(
+        &mut self,
+        query: &str,
+        vars: impl Into<Vars>,
+    ) -> Result<api::Response, DgraphError> {
+        let vars = vars.into();
+        vars.check(query)?;
+
+        let mut request = api::Request {
+            query: query.to_string(),
+            vars: vars.into(),
+            start_ts: self.context.get_start_ts(),
+            read_only: self.read_only,
+            best_effort: self.best_effort,
+            ..Default::default()
+        };
+
+        self.do_request_async(&mut request).await
+    }
+
+    pub async fn mutate_async(&mut self, mu: api::Mutation) -> Result<api::Response, DgraphError> {
+        let mut request = api::Request::new();
+        let commit_now = mu.get_commit_now();
+
+        request.set_mutations(vec![mu].into());
+        request.set_commit_now(commit_now);
+
+        self.do_request_async(&mut request).await
+    }
+
+    pub async fn do_request_async(
+        &mut self,
+        request: &mut api::Request,
+    ) -> Result<api::Response, DgraphError> {
+        self.prepare_request(request)?;
+
+        let response = match self
+            .endpoint()
+            .query_async(request, self.dgraph.call_option(self.timeout)?)
+            .await
+        {
+            Ok(response) => response,
+            Err(err) => {
+                let retry_result = if self.dgraph.is_jwt_expired(&err) {
+                    match self.dgraph.retry_login_async_with(self.timeout).await {
+                        Ok(_) => match self.dgraph.call_option(self.timeout) {
+                            Ok(opt) => self
+                                .endpoint()
+                                .query_async(request, opt)
+                                .await
+                                .map_err(DgraphError::from),
+                            Err(err) => Err(err),
+                        },
+                        Err(err) => Err(err),
+                    }
+                } else {
+                    Err(err.into())
+                };
+
+                match retry_result {
+                    Ok(response) => response,
+                    Err(err) => {
+                        let _ = self.discard_async().await;
+                        return Err(err);
+                    }
+                }
+            }
+        };
+
+        self.finish_request(request, &response)?;
+
+        Ok(response)
+    }
+
+    pub async fn commit_async(mut self) -> Result<(), DgraphError> {
+        match (self.finished, self.read_only) {
+            (true, _) => return Err(DgraphError::TxnFinished),
+            (_, true) => return Err(DgraphError::TxnReadOnly),
+            _ => (),
+        }
+
+        self.commit_or_abort_async().await
+    }
+
+    pub async fn discard_async(&mut self) -> Result<(), DgraphError> {
+        self.context.aborted = true;
+        self.commit_or_abort_async().await
+    }
+
+    async fn commit_or_abort_async(&mut self) -> Result<(), DgraphError> {
+        if self.finished {
+            return Ok(());
+        }
+        self.finished = true;
+
+        if !self.mutated {
+            return Ok(());
+        }
+
+        let res = self
+            .endpoint()
+            .commit_or_abort_async(&self.context, self.dgraph.call_option(self.timeout)?)
+            .await;
+
+        match res {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                if self.dgraph.is_jwt_expired(&err) {
+                    self.dgraph.retry_login_async_with(self.timeout).await?;
+                    self.endpoint()
+                        .commit_or_abort_async(
+                            &self.context,
+                            self.dgraph.call_option(self.timeout)?,
+                        )
+                        .await?;
+
+                    Ok(())
+                } else {
+                    Err(err.into())
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::errors::DgraphError;
+
+/// The UID of a Dgraph node.
+///
+/// Parses from the `0x` hex form Dgraph uses as well as from decimal, and displays and
+/// serializes as `0x..`, like the `uid` fields of a JSON response. Converting into `String`
+/// gives the form expected by `NQuad` subjects and object ids, and into `u64` the form used
+/// by `Value_oneof_val::uid_val` and `Uids`.
+///
+/// ```
+/// use dgraph::Uid;
+///
+/// let uid: Uid = "0x1a".parse().unwrap();
+///
+/// assert_eq!(uid, Uid(26));
+/// assert_eq!(uid.to_string(), "0x1a");
+///
+/// let mut nquad = dgraph::NQuad::new();
+/// nquad.set_subject(uid.into());
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Uid(pub u64);
+
+impl Uid {
+    pub fn as_u64(self) -> u64 {
+        self.0
+    }
+}
+
+impl FromStr for Uid {
+    type Err = DgraphError;
+
+    fn from_str(uid: &str) -> Result<Uid, DgraphError> {
+        let parsed = if uid.starts_with("0x") || uid.starts_with("0X") {
+            u64::from_str_radix(&uid[2..], 16)
+        } else {
+            uid.parse()
+        };
+
+        parsed
+            .map(Uid)
+            .map_err(|_| DgraphError::InvalidUid(uid.to_string()))
+    }
+}
+
+impl fmt::Display for Uid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#x}", self.0)
+    }
+}
+
+impl fmt::LowerHex for Uid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.0, f)
+    }
+}
+
+impl From<u64> for Uid {
+    fn from(uid: u64) -> Uid {
+        Uid(uid)
+    }
+}
+
+impl From<Uid> for u64 {
+    fn from(uid: Uid) -> u64 {
+        uid.0
+    }
+}
+
+impl From<Uid> for String {
+    fn from(uid: Uid) -> String {
+        uid.to_string()
+    }
+}
+
+impl Serialize for Uid {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Uid {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Uid, D::Error> {
+        deserializer.deserialize_any(UidVisitor)
+    }
+}
+
+/// Accepts the `"0x.."` strings Dgraph returns as well as plain numbers.
+struct UidVisitor;
+
+impl<'de> Visitor<'de> for UidVisitor {
+    type Value = Uid;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a uid as a hex or decimal string or as a number")
+    }
+
+    fn visit_u64<E: de::Error>(self, uid: u64) -> Result<Uid, E> {
+        Ok(Uid(uid))
+    }
+
+    fn visit_i64<E: de::Error>(self, uid: i64) -> Result<Uid, E> {
+        if uid < 0 {
+            return Err(E::invalid_value(de::Unexpected::Signed(uid), &self));
+        }
+        Ok(Uid(uid as u64))
+    }
+
+    fn visit_str<E: de::Error>(self, uid: &str) -> Result<Uid, E> {
+        uid.parse()
+            .map_err(|_| E::invalid_value(de::Unexpected::Str(uid), &self))
+    }
+}
@@ -0,0 +1,215 @@
+//! A record of the UIDs given to blank nodes and external ids, kept across loads.
+
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use crate::errors::DgraphError;
+use crate::protos::api;
+use crate::uid::Uid;
+
+/// Maps blank nodes (`_:alice`) and external ids (`<alice>`) to the UIDs Dgraph assigned
+/// them, like the live loader's `--xidmap`, so that loading the same data again updates the
+/// existing nodes instead of creating duplicates.
+///
+/// Keys are the names found in `Response.uids`: blank node names without their `_:`
+/// prefix, and external ids as written. A map opened from a file appends every new
+/// assignment to it as a `name<TAB>uid` line.
+///
+/// ```no_run
+/// use dgraph::{rdf, Mutation, UidMap};
+///
+/// # fn main() -> Result<(), dgraph::DgraphError> {
+/// # let dgraph = dgraph::make_dgraph!(dgraph::new_dgraph_client("localhost:9080"));
+/// let mut uids = UidMap::open("xidmap.tsv")?;
+///
+/// let mut nquads = rdf::parse(r#"<alice> <name> "Alice" ."#)?;
+/// uids.rewrite_nquads(&mut nquads);
+///
+/// let mut mu = Mutation::new();
+/// mu.set = nquads.into();
+/// mu.commit_now = true;
+/// let response = dgraph.new_txn()?.mutate(mu)?;
+/// uids.record(&response)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct UidMap {
+    uids: HashMap<String, Uid>,
+    file: Option<BufWriter<File>>,
+}
+
+impl UidMap {
+    /// An in-memory map.
+    pub fn new() -> UidMap {
+        Default::default()
+    }
+
+    /// Reads the assignments already in the file at `path`, creating it if needed, and
+    /// appends new ones to it.
+    pub fn open(path: impl AsRef<Path>) -> Result<UidMap, DgraphError> {
+        let path = path.as_ref();
+        let mut uids = HashMap::new();
+
+        if path.exists() {
+            for (index, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+                let line = line?;
+                if line.is_empty() {
+                    continue;
+                }
+
+                let entry = line.rfind('\t').and_then(|tab| {
+                    Some((unescape(&line[..tab]), line[tab + 1..].parse::<Uid>().ok()?))
+                });
+                match entry {
+                    Some((name, uid)) => {
+                        uids.insert(name, uid);
+                    }
+                    None => {
+                        return Err(DgraphError::Io(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("invalid entry at {}:{}", path.display(), index + 1),
+                        )))
+                    }
+                }
+            }
+        }
+
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(UidMap {
+            uids,
+            file: Some(BufWriter::new(file)),
+        })
+    }
+
+    pub fn get(&self, name: &str) -> Option<Uid> {
+        self.uids.get(name).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.uids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.uids.is_empty()
+    }
+
+    /// Records an assignment, appending it to the file of a map opened with `open`.
+    pub fn insert(&mut self, name: impl Into<String>, uid: Uid) -> Result<(), DgraphError> {
+        let name = name.into();
+
+        if self.uids.get(&name) != Some(&uid) {
+            if let Some(file) = &mut self.file {
+                writeln!(file, "{}\t{}", escape(&name), uid)?;
+            }
+            self.uids.insert(name, uid);
+        }
+
+        Ok(())
+    }
+
+    /// Records every UID assigned by a mutation.
+    pub fn record(&mut self, response: &api::Response) -> Result<(), DgraphError> {
+        for (name, uid) in &response.uids {
+            self.insert(name.as_str(), uid.parse()?)?;
+        }
+
+        self.flush()
+    }
+
+    /// Writes buffered assignments to the file. Also done when the map is dropped.
+    pub fn flush(&mut self) -> Result<(), DgraphError> {
+        if let Some(file) = &mut self.file {
+            file.flush()?;
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the subjects and objects of `nquads` that are known blank nodes or external
+    /// ids with their UIDs. Unknown external ids become blank nodes of the same name, so
+    /// `record` picks up the UIDs they get.
+    pub fn rewrite_nquads(&self, nquads: &mut [api::NQuad]) {
+        for nquad in nquads {
+            self.rewrite_node(&mut nquad.subject);
+            self.rewrite_node(&mut nquad.object_id);
+        }
+    }
+
+    /// Rewrites the `uid` fields of mutation JSON, at any depth, like `rewrite_nquads`.
+    pub fn rewrite_json(&self, value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::Object(object) => {
+                for (key, value) in object.iter_mut() {
+                    match value {
+                        serde_json::Value::String(uid) if key == "uid" => self.rewrite_node(uid),
+                        value => self.rewrite_json(value),
+                    }
+                }
+            }
+            serde_json::Value::Array(values) => {
+                for value in values {
+                    self.rewrite_json(value);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn rewrite_node(&self, node: &mut String) {
+        let rewritten = match node_name(node) {
+            Some(name) => match self.get(name) {
+                Some(uid) => uid.to_string(),
+                None if node.starts_with("_:") => return,
+                None => format!("_:{}", name),
+            },
+            None => return,
+        };
+
+        *node = rewritten;
+    }
+}
+
+/// The name a subject or object is recorded under: blank nodes without their `_:` prefix,
+/// and external ids as they are. UIDs, `uid(v)`/`val(v)` variables and empty nodes have none.
+pub(crate) fn node_name(node: &str) -> Option<&str> {
+    if let Some(name) = node.strip_prefix("_:") {
+        return Some(name);
+    }
+
+    let variable = node.starts_with("uid(") || node.starts_with("val(");
+    if node.is_empty() || variable || node.parse::<Uid>().is_ok() {
+        None
+    } else {
+        Some(node)
+    }
+}
+
+fn escape(name: &str) -> String {
+    name.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+}
+
+fn unescape(name: &str) -> String {
+    let mut unescaped = String::with_capacity(name.len());
+    let mut chars = name.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some(c) => unescaped.push(c),
+            None => unescaped.push('\\'),
+        }
+    }
+
+    unescaped
+}
@@ -0,0 +1,236 @@
+use serde::Serialize;
+
+use crate::errors::DgraphError;
+use crate::protos::api;
+use crate::response::Assigned;
+use crate::vars::Vars;
+
+/// An upsert block: a query whose variables feed any number of conditional mutations, all
+/// run in a single request by `Txn::upsert`.
+///
+/// Each mutation method appends a mutation; `when` attaches a condition to the mutation added
+/// last. A condition given before any mutation makes `Txn::upsert` fail with
+/// `DgraphError::InvalidDql` instead of running the mutations unconditionally.
+///
+/// ```no_run
+/// # fn main() -> Result<(), dgraph::DgraphError> {
+/// # let dgraph = dgraph::make_dgraph!(dgraph::new_dgraph_client("localhost:9080"));
+/// let upsert = dgraph::Upsert::new(r#"{ v as var(func: eq(email, "alice@example.com")) }"#)
+///     .set_nquads(r#"_:alice <email> "alice@example.com" ."#)
+///     .when("@if(eq(len(v), 0))")
+///     .set_nquads(r#"uid(v) <name> "Alice" ."#)
+///     .when("@if(eq(len(v), 1))")
+///     .commit_now();
+///
+/// let response = dgraph.new_txn()?.upsert(upsert)?;
+/// let created = response.mutations()[0].uid("alice");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Upsert {
+    query: String,
+    vars: Vars,
+    mutations: Vec<api::Mutation>,
+    blank_nodes: Vec<Vec<String>>,
+    commit_now: bool,
+    orphan_cond: Option<String>,
+}
+
+impl Upsert {
+    pub fn new(query: impl Into<String>) -> Upsert {
+        Upsert {
+            query: query.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn vars(mut self, vars: impl Into<Vars>) -> Self {
+        self.vars = vars.into();
+        self
+    }
+
+    pub fn set_json<T: Serialize>(self, value: &T) -> Result<Self, DgraphError> {
+        let value = serde_json::to_value(value).map_err(DgraphError::Serialize)?;
+        let mut blank_nodes = Vec::new();
+        json_blank_nodes(&value, &mut blank_nodes);
+
+        let mut mu = api::Mutation::new();
+        mu.set_set_json(value.to_string().into_bytes());
+        Ok(self.push(mu, blank_nodes))
+    }
+
+    pub fn delete_json<T: Serialize>(self, value: &T) -> Result<Self, DgraphError> {
+        let json = serde_json::to_vec(value).map_err(DgraphError::Serialize)?;
+
+        let mut mu = api::Mutation::new();
+        mu.set_delete_json(json);
+        Ok(self.push(mu, Vec::new()))
+    }
+
+    pub fn set_nquads(self, nquads: impl Into<String>) -> Self {
+        let nquads = nquads.into();
+        let blank_nodes = nquad_blank_nodes(&nquads);
+
+        let mut mu = api::Mutation::new();
+        mu.set_set_nquads(nquads.into_bytes());
+        self.push(mu, blank_nodes)
+    }
+
+    pub fn delete_nquads(self, nquads: impl Into<String>) -> Self {
+        let mut mu = api::Mutation::new();
+        mu.set_del_nquads(nquads.into().into_bytes());
+        self.push(mu, Vec::new())
+    }
+
+    /// Appends a prepared mutation, for example one using the structured `set`/`del` fields.
+    pub fn mutation(self, mu: api::Mutation) -> Self {
+        self.push(mu, Vec::new())
+    }
+
+    /// Makes the mutation added last conditional, e.g. `@if(eq(len(v), 0))`.
+    pub fn when(mut self, cond: impl Into<String>) -> Self {
+        match self.mutations.last_mut() {
+            Some(mu) => mu.set_cond(cond.into()),
+            None => self.orphan_cond = Some(cond.into()),
+        }
+        self
+    }
+
+    /// Commits the transaction along with the upsert.
+    pub fn commit_now(mut self) -> Self {
+        self.commit_now = true;
+        self
+    }
+
+    fn push(mut self, mu: api::Mutation, blank_nodes: Vec<String>) -> Self {
+        self.mutations.push(mu);
+        self.blank_nodes.push(blank_nodes);
+        self
+    }
+
+    pub(crate) fn into_request(self) -> Result<(api::Request, Vec<Vec<String>>), DgraphError> {
+        if let Some(cond) = self.orphan_cond {
+            return Err(DgraphError::InvalidDql(format!(
+                "condition `{}` must follow the mutation it guards",
+                cond
+            )));
+        }
+        self.vars.check(&self.query)?;
+
+        let request = api::Request {
+            query: self.query,
+            vars: self.vars.into(),
+            mutations: self.mutations.into(),
+            commit_now: self.commit_now,
+            ..Default::default()
+        };
+
+        Ok((request, self.blank_nodes))
+    }
+}
+
+/// The outcome of `Txn::upsert`.
+#[derive(Debug)]
+pub struct UpsertResponse {
+    response: api::Response,
+    assigned: Assigned,
+    mutations: Vec<Assigned>,
+}
+
+impl UpsertResponse {
+    pub(crate) fn new(
+        response: api::Response,
+        blank_nodes: Vec<Vec<String>>,
+    ) -> Result<UpsertResponse, DgraphError> {
+        let assigned = Assigned::from_response(&response)?;
+        let mutations = blank_nodes
+            .into_iter()
+            .map(|names| Assigned {
+                uids: names
+                    .into_iter()
+                    .filter_map(|name| assigned.uid(&name).map(|uid| (name, uid)))
+                    .collect(),
+                context: assigned.context.clone(),
+            })
+            .collect();
+
+        Ok(UpsertResponse {
+            response,
+            assigned,
+            mutations,
+        })
+    }
+
+    /// The UIDs assigned to blank nodes across all mutations.
+    pub fn assigned(&self) -> &Assigned {
+        &self.assigned
+    }
+
+    /// One entry per mutation, in the order they were added, holding the UIDs assigned to
+    /// the blank nodes that mutation mentions. Blank nodes of a mutation whose condition did
+    /// not hold are missing.
+    pub fn mutations(&self) -> &[Assigned] {
+        &self.mutations
+    }
+
+    /// The JSON result of the upsert's query block.
+    pub fn json(&self) -> &[u8] {
+        &self.response.json
+    }
+
+    pub fn response(&self) -> &api::Response {
+        &self.response
+    }
+}
+
+fn json_blank_nodes(value: &serde_json::Value, names: &mut Vec<String>) {
+    match value {
+        serde_json::Value::Object(object) => {
+            for (key, value) in object {
+                match value {
+                    serde_json::Value::String(uid) if key == "uid" && uid.starts_with("_:") => {
+                        names.push(uid[2..].to_string())
+                    }
+                    value => json_blank_nodes(value, names),
+                }
+            }
+        }
+        serde_json::Value::Array(values) => {
+            for value in values {
+                json_blank_nodes(value, names);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// The blank nodes mentioned by RDF text, skipping anything inside quoted literals.
+fn nquad_blank_nodes(nquads: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    let mut chars = nquads.char_indices();
+    let mut quoted = false;
+
+    while let Some((start, c)) = chars.next() {
+        match c {
+            '\\' if quoted => {
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            '_' if !quoted && nquads[start..].starts_with("_:") => {
+                let name: String = nquads[start + 2..]
+                    .chars()
+                    .take_while(|c| c.is_alphanumeric() || *c == '_' || *c == '-' || *c == '.')
+                    .collect();
+                let name = name.trim_end_matches('.');
+
+                if !name.is_empty() && !names.iter().any(|known| known == name) {
+                    names.push(name.to_string());
+                }
+            }
+            _ => (),
+        }
+    }
+
+    names
+}
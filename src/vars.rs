@@ -0,0 +1,223 @@
+use std::collections::{BTreeMap, HashMap};
+use std::time::SystemTime;
+
+use crate::datetime;
+use crate::errors::DgraphError;
+use crate::uid::Uid;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum VarType {
+    Int,
+    Float,
+    Bool,
+    String,
+    /// Given as plain text, e.g. through a `HashMap<String, String>`.
+    Untyped,
+}
+
+impl VarType {
+    /// Whether a value of this type may be passed to a variable declared as `declared`.
+    fn fits(self, declared: &str) -> bool {
+        match (self, declared) {
+            (VarType::Untyped, _) => true,
+            (VarType::Int, "int") | (VarType::Int, "float") => true,
+            (VarType::Float, "float") => true,
+            (VarType::Bool, "bool") => true,
+            (VarType::String, "string") => true,
+            (_, "int") | (_, "float") | (_, "bool") | (_, "string") => false,
+            _ => true,
+        }
+    }
+}
+
+/// Typed variables for a DQL query such as `query all($age: int, $u: string) { ... }`.
+///
+/// Names get their `$` prefix added when it is missing, and values are formatted the way
+/// Dgraph parses them. `Txn::query_with_vars` checks the variables against the query header
+/// before sending the request.
+///
+/// ```
+/// let vars = dgraph::Vars::new().int("age", 26).string("$name", "Alice");
+///
+/// assert_eq!(vars.get("$age"), Some("26"));
+/// assert!(vars.check("query q($age: int, $name: string) { q() {} }").is_ok());
+/// assert!(vars.check("query q($age: int) { q() {} }").is_err());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Vars {
+    vars: BTreeMap<String, (VarType, String)>,
+}
+
+impl Vars {
+    pub fn new() -> Vars {
+        Default::default()
+    }
+
+    pub fn int(self, name: &str, value: i64) -> Self {
+        self.with(name, VarType::Int, value.to_string())
+    }
+
+    pub fn float(self, name: &str, value: f64) -> Self {
+        self.with(name, VarType::Float, value.to_string())
+    }
+
+    pub fn bool(self, name: &str, value: bool) -> Self {
+        self.with(name, VarType::Bool, value.to_string())
+    }
+
+    pub fn string(self, name: &str, value: impl Into<String>) -> Self {
+        self.with(name, VarType::String, value.into())
+    }
+
+    /// Passes a UID in its `0x` hex form, for use with `uid($name)`.
+    pub fn uid(self, name: &str, value: impl Into<Uid>) -> Self {
+        self.with(name, VarType::String, value.into().to_string())
+    }
+
+    /// Passes a point in time as an RFC 3339 string in UTC.
+    pub fn datetime(self, name: &str, value: SystemTime) -> Self {
+        self.with(name, VarType::String, datetime::to_rfc3339(value))
+    }
+
+    fn with(mut self, name: &str, kind: VarType, value: String) -> Self {
+        self.vars.insert(prefixed(name), (kind, value));
+        self
+    }
+
+    /// Returns the formatted value of a variable, given with or without its `$` prefix.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.vars
+            .get(&prefixed(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.vars.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vars.is_empty()
+    }
+
+    /// Checks that every typed variable is declared in the `query name(...)` header of
+    /// `query` with a matching type. Queries without such a header, and variables given as
+    /// plain text through a `HashMap<String, String>`, are left for the server to check, as
+    /// are declared variables without a value.
+    pub fn check(&self, query: &str) -> Result<(), DgraphError> {
+        match declared_vars(query) {
+            Some(declared) => self.check_declared(&declared),
+            None => Ok(()),
+        }
+    }
+
+    /// Like `check`, but rejects typed variables when `query` has no header at all.
+    pub(crate) fn check_header(&self, query: &str) -> Result<(), DgraphError> {
+        self.check_declared(&declared_vars(query).unwrap_or_default())
+    }
+
+    fn check_declared(&self, declared: &[(String, String)]) -> Result<(), DgraphError> {
+        for (name, (kind, _)) in &self.vars {
+            if *kind == VarType::Untyped {
+                continue;
+            }
+
+            let declared_type = match declared.iter().find(|(declared, _)| declared == name) {
+                Some((_, declared_type)) => declared_type,
+                None => {
+                    return Err(DgraphError::InvalidVars(format!(
+                        "variable `{}` is not declared in the query header",
+                        name
+                    )))
+                }
+            };
+
+            if !kind.fits(declared_type) {
+                return Err(DgraphError::InvalidVars(format!(
+                    "variable `{}` is declared as `{}` but was given a {:?} value",
+                    name, declared_type, kind
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Keeps the names and values as given: they are neither prefixed nor checked.
+impl From<HashMap<String, String>> for Vars {
+    fn from(vars: HashMap<String, String>) -> Vars {
+        let vars = vars
+            .into_iter()
+            .map(|(name, value)| (name, (VarType::Untyped, value)))
+            .collect();
+
+        Vars { vars }
+    }
+}
+
+impl From<Vars> for HashMap<String, String> {
+    fn from(vars: Vars) -> HashMap<String, String> {
+        vars.vars
+            .into_iter()
+            .map(|(name, (_, value))| (name, value))
+            .collect()
+    }
+}
+
+fn prefixed(name: &str) -> String {
+    if name.starts_with('$') {
+        name.to_string()
+    } else {
+        format!("${}", name)
+    }
+}
+
+/// Reads the `($name: type = default, ...)` list of a `query name(...)` header, returning the
+/// declared names and their lowercase types without the `!` marker, or `None` if the query
+/// has no such header.
+fn declared_vars(query: &str) -> Option<Vec<(String, String)>> {
+    let query = query.trim_start();
+    if !query.starts_with("query") {
+        return None;
+    }
+
+    let header = &query["query".len()..];
+    let open = match header.find(|c| c == '(' || c == '{') {
+        Some(open) if header[open..].starts_with('(') => open,
+        _ => return None,
+    };
+
+    let mut declarations = Vec::new();
+    let mut current = String::new();
+    let mut in_string = false;
+
+    for c in header[open + 1..].chars() {
+        match c {
+            '"' => {
+                in_string = !in_string;
+                current.push(c);
+            }
+            ',' | ')' if !in_string => {
+                declarations.push(std::mem::replace(&mut current, String::new()));
+                if c == ')' {
+                    break;
+                }
+            }
+            c => current.push(c),
+        }
+    }
+
+    let declared = declarations
+        .iter()
+        .filter_map(|declaration| {
+            let declaration = declaration.split('=').next()?;
+            let mut parts = declaration.splitn(2, ':');
+            let name = parts.next()?.trim();
+            let kind = parts.next()?.trim().trim_end_matches('!').to_lowercase();
+
+            Some((name.to_string(), kind))
+        })
+        .collect();
+
+    Some(declared)
+}
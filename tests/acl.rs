@@ -0,0 +1,47 @@
+//! Runs against the ACL alpha of `docker-compose-20-03.yaml`, so these tests are ignored
+//! unless run with `--ignored`.
+
+use dgraph::{make_dgraph, Dgraph};
+
+mod common;
+
+/// Needs a valid `accessJwt`, since ACL rejects queries without one.
+const QUERY: &str = "{ q(func: has(dgraph.xid)) { uid } }";
+
+fn logged_in() -> Dgraph {
+    let mut dgraph = make_dgraph!(dgraph::new_dgraph_client(common::ACL_DGRAPH_URL));
+    dgraph
+        .login("groot".to_string(), "password".to_string())
+        .unwrap();
+    dgraph
+}
+
+#[test]
+#[ignore]
+fn it_rejects_queries_before_login() {
+    let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::ACL_DGRAPH_URL));
+
+    assert!(dgraph.new_readonly_txn().unwrap().query(QUERY).is_err());
+}
+
+#[test]
+#[ignore]
+fn it_attaches_the_access_jwt_after_login() {
+    let dgraph = logged_in();
+
+    assert!(dgraph.new_readonly_txn().unwrap().query(QUERY).is_ok());
+}
+
+#[test]
+#[ignore]
+fn it_refreshes_an_expired_access_jwt_in_a_txn() {
+    let dgraph = logged_in();
+    assert!(dgraph.new_readonly_txn().unwrap().query(QUERY).is_ok());
+
+    std::thread::sleep(common::ACL_ACCESS_TTL * 2);
+
+    // Fails as unauthenticated unless the refreshed token is attached to the retried query.
+    let mut txn = dgraph.new_readonly_txn().unwrap();
+    assert!(txn.query(QUERY).is_ok());
+    assert!(txn.query(QUERY).is_ok());
+}
@@ -1,10 +1,10 @@
-use dgraph::{make_dgraph, Dgraph, Operation};
+use dgraph::{make_dgraph, Dgraph, DgraphError, LeastInFlight, Operation, RoundRobin};
 
 mod common;
 
 fn is_connected(dgraph: &Dgraph) -> bool {
     let q = "schema {}".to_string();
-    let response = dgraph.new_readonly_txn().query(&q);
+    let response = dgraph.new_readonly_txn().unwrap().query(&q);
 
     response.is_ok()
 }
@@ -16,6 +16,78 @@ fn it_connects() {
     assert_eq!(is_connected(&dgraph), true);
 }
 
+#[test]
+fn it_connects_through_builder() {
+    let dgraph = Dgraph::builder()
+        .endpoint(common::DGRAPH_URL)
+        .completion_queues(2)
+        .build()
+        .expect("Failed to build client.");
+
+    assert_eq!(is_connected(&dgraph), true);
+}
+
+#[test]
+fn it_does_not_build_without_endpoints() {
+    let result = Dgraph::builder().build();
+
+    let error_matched = match result {
+        Err(DgraphError::NoClients) => true,
+        _ => false,
+    };
+    assert!(error_matched);
+}
+
+#[test]
+fn it_ejects_unavailable_endpoints() {
+    let dgraph = Dgraph::with_balancer(
+        vec![
+            dgraph::new_dgraph_client("no_dgraph_url:9080"),
+            dgraph::new_dgraph_client(common::DGRAPH_URL),
+        ],
+        RoundRobin::default(),
+    );
+
+    // The first round may land on the unreachable endpoint, which then gets ejected.
+    for _ in 0..2 {
+        let _ = is_connected(&dgraph);
+    }
+
+    for _ in 0..4 {
+        assert_eq!(is_connected(&dgraph), true);
+    }
+}
+
+#[test]
+fn it_connects_with_least_in_flight_balancer() {
+    let dgraph = Dgraph::builder()
+        .endpoint(common::DGRAPH_URL)
+        .endpoint(common::DGRAPH_URL)
+        .balancer(LeastInFlight)
+        .health_check_interval(std::time::Duration::from_secs(1))
+        .build()
+        .expect("Failed to build client.");
+
+    assert_eq!(is_connected(&dgraph), true);
+}
+
+#[test]
+fn it_ejects_unreachable_endpoints_through_health_checks() {
+    let interval = std::time::Duration::from_millis(200);
+    let dgraph = Dgraph::builder()
+        .endpoint(common::DGRAPH_URL)
+        .endpoint("localhost:1")
+        .health_check_interval(interval)
+        .build()
+        .expect("Failed to build client.");
+    dgraph.start_health_checks(interval);
+
+    std::thread::sleep(interval * 5);
+    for _ in 0..4 {
+        assert_eq!(is_connected(&dgraph), true);
+    }
+}
+
 #[test]
 fn it_does_not_connect_to_wrong_url() {
     let dgraph = make_dgraph!(dgraph::new_dgraph_client("no_dgraph_url:9080"));
@@ -36,18 +108,149 @@ fn it_alters_schema() {
 }
 
 #[test]
-#[should_panic]
+fn it_returns_schema_error_for_invalid_schema() {
+    let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
+
+    let result = dgraph.alter(&Operation {
+        schema: "something: nosuchtype .".to_string(),
+        ..Default::default()
+    });
+
+    let error_matched = match result.unwrap_err() {
+        DgraphError::SchemaError(_) => true,
+        _ => false,
+    };
+    assert!(error_matched);
+}
+
+#[test]
 fn it_does_not_alter_without_client() {
     let dgraph = make_dgraph!();
-    let _ = dgraph.alter(&Operation {
+    let result = dgraph.alter(&Operation {
         schema: "something: string .".to_string(),
         ..Default::default()
     });
+
+    let error_matched = match result.unwrap_err() {
+        DgraphError::NoClients => true,
+        _ => false,
+    };
+    assert!(error_matched);
 }
 
 #[test]
-#[should_panic]
 fn it_does_not_crate_transaction_without_client() {
     let dgraph = make_dgraph!();
-    dgraph.new_txn();
+    let result = dgraph.new_txn();
+
+    let error_matched = match result {
+        Err(DgraphError::NoClients) => true,
+        _ => false,
+    };
+    assert!(error_matched);
+}
+
+#[test]
+fn it_does_not_login_without_client() {
+    let mut dgraph = make_dgraph!();
+    let result = dgraph.login("groot".to_string(), "password".to_string());
+
+    let error_matched = match result.unwrap_err() {
+        DgraphError::NoClients => true,
+        _ => false,
+    };
+    assert!(error_matched);
+}
+
+#[test]
+fn it_sends_calls_without_access_jwt_metadata_before_login() {
+    let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
+    let op = Operation {
+        schema: "something: string .".to_string(),
+        ..Default::default()
+    };
+
+    assert!(dgraph.alter(&op).is_ok());
+    assert!(futures::executor::block_on(dgraph.alter_async(&op)).is_ok());
+
+    let mut txn = dgraph.new_txn().unwrap();
+    let mut mu = dgraph::Mutation::new();
+    mu.set_set_nquads(br#"_:thing <something> "with no jwt" ."#.to_vec());
+    assert!(txn.mutate(mu).is_ok());
+    assert!(txn.commit().is_ok());
+}
+
+/// Serves a first login with tokens, answers every query as unauthenticated and never answers
+/// the login that refreshes the JWT.
+fn serve_stalled_refresh() -> dgraph::grpcio::Server {
+    use dgraph::grpcio::{
+        Environment, RpcStatus, RpcStatusCode, ServerBuilder, ServiceBuilder, UnarySink,
+    };
+    use futures::FutureExt;
+    use protobuf::Message;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let logins = Arc::new(AtomicUsize::new(0));
+    let service = ServiceBuilder::new()
+        .add_unary_handler(
+            &dgraph::METHOD_DGRAPH_LOGIN,
+            move |ctx, _: dgraph::LoginRequest, sink: UnarySink<dgraph::Response>| {
+                if logins.fetch_add(1, Ordering::SeqCst) > 0 {
+                    std::mem::forget(sink);
+                    return;
+                }
+                let jwt = dgraph::Jwt {
+                    access_jwt: "access".to_string(),
+                    refresh_jwt: "refresh".to_string(),
+                    ..Default::default()
+                };
+                let response = dgraph::Response {
+                    json: jwt.write_to_bytes().unwrap(),
+                    ..Default::default()
+                };
+                ctx.spawn(sink.success(response).map(|_| ()));
+            },
+        )
+        .add_unary_handler(
+            &dgraph::METHOD_DGRAPH_QUERY,
+            |ctx, _: dgraph::Request, sink: UnarySink<dgraph::Response>| {
+                let status = RpcStatus::new(
+                    RpcStatusCode::UNAUTHENTICATED,
+                    Some("Token is expired".to_string()),
+                );
+                ctx.spawn(sink.fail(status).map(|_| ()));
+            },
+        )
+        .build();
+
+    let mut server = ServerBuilder::new(Arc::new(Environment::new(1)))
+        .register_service(service)
+        .bind("127.0.0.1", 0)
+        .build()
+        .unwrap();
+    server.start();
+    server
+}
+
+#[test]
+fn it_applies_the_txn_deadline_to_jwt_refresh() {
+    let server = serve_stalled_refresh();
+    let (host, port) = server.bind_addrs().next().unwrap();
+    let dgraph = Dgraph::builder()
+        .endpoint(format!("{}:{}", host, port))
+        .login("groot", "password")
+        .timeout(std::time::Duration::from_secs(5))
+        .build()
+        .unwrap();
+
+    let mut txn = dgraph
+        .new_readonly_txn()
+        .unwrap()
+        .with_timeout(std::time::Duration::from_millis(200));
+    let error_matched = match txn.query("schema {}").unwrap_err() {
+        DgraphError::DeadlineExceeded => true,
+        _ => false,
+    };
+    assert!(error_matched);
 }
@@ -1 +1,9 @@
-pub const DGRAPH_URL: &str = "localhost:19080";
+// Each test binary uses only some of these.
+#![allow(dead_code)]
+
+pub const DGRAPH_URL: &str = "localhost:19080";
+
+/// An alpha running with ACL enabled and a 3s access JWT TTL, started only by
+/// `docker-compose-20-03.yaml`.
+pub const ACL_DGRAPH_URL: &str = "localhost:29080";
+pub const ACL_ACCESS_TTL: std::time::Duration = std::time::Duration::from_secs(3);
@@ -0,0 +1,177 @@
+use dgraph::dql::{not, Block, Filter, Func, Query};
+use dgraph::{make_dgraph, DgraphError, Uid};
+use std::collections::HashMap;
+
+mod common;
+
+#[test]
+fn it_renders_values_as_vars() {
+    let (query, vars) = Query::new()
+        .block(
+            Block::root("people", Func::eq("name", "Alice"))
+                .filter(Func::ge("age", 18).and(not(Func::has("deleted"))))
+                .first(10)
+                .order_asc("name")
+                .fields(&["uid", "name"]),
+        )
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        query,
+        "query q($v0: string, $v1: int, $v2: int) {\n  \
+         people(func: eq(name, $v0), first: $v1, orderasc: name) \
+         @filter(ge(age, $v2) AND NOT has(deleted)) { uid name }\n}"
+    );
+
+    let vars: HashMap<String, String> = vars.into();
+    assert_eq!(vars["$v0"], "Alice");
+    assert_eq!(vars["$v1"], "10");
+    assert_eq!(vars["$v2"], "18");
+}
+
+#[test]
+fn it_renders_pages_expanding_all_predicates() {
+    let (query, vars) = Query::new()
+        .block(
+            Block::root("page", Func::dgraph_type("Person"))
+                .first(100)
+                .after(Uid(0x2a))
+                .field("uid")
+                .expand_all(&["uid", "name"]),
+        )
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        query,
+        "query q($v0: int, $v1: string) {\n  \
+         page(func: type(Person), first: $v0, after: $v1) { uid expand(_all_) { uid name } }\n}"
+    );
+
+    let vars: HashMap<String, String> = vars.into();
+    assert_eq!(vars["$v1"], "0x2a");
+}
+
+#[test]
+fn it_renders_nested_edges_and_directives() {
+    let (query, _) = Query::new()
+        .block(Block::var(Func::anyofterms("name", "Alice Bob")).as_var("people"))
+        .block(
+            Block::root("friends", Func::uid_var("people"))
+                .normalize()
+                .cascade()
+                .field_as("person", "name")
+                .edge(
+                    Block::edge("friend")
+                        .alias("knows")
+                        .facet("close")
+                        .order_desc("age")
+                        .field_as("friend_name", "name"),
+                ),
+        )
+        .block(
+            Block::root("tree", Func::uid(vec![Uid(1), Uid(2)]))
+                .recurse(3)
+                .fields(&["name", "~friend"]),
+        )
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        query,
+        "query q($v0: string, $v1: string) {\n  \
+         people as var(func: anyofterms(name, $v0))\n  \
+         friends(func: uid(people)) @cascade @normalize { person: name \
+         knows: friend(orderdesc: age) @facets(close) { friend_name: name } }\n  \
+         tree(func: uid($v1)) @recurse(depth: 3) { name ~friend }\n}"
+    );
+}
+
+#[test]
+fn it_renders_geo_functions_with_literal_coordinates() {
+    let (query, vars) = Query::new()
+        .block(Block::root("near", Func::near("loc", [16.37, 48.2], 1000.0)).field("name"))
+        .block(
+            Block::root(
+                "within",
+                Func::within("loc", vec![[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 0.0]]),
+            )
+            .field("name"),
+        )
+        .build()
+        .unwrap();
+
+    assert!(vars.is_empty());
+    assert_eq!(
+        query,
+        "{\n  near(func: near(loc, [16.37, 48.2], 1000)) { name }\n  \
+         within(func: within(loc, [[[0, 0], [1, 0], [1, 1], [0, 0]]])) { name }\n}"
+    );
+}
+
+#[test]
+fn it_rejects_names_that_would_change_the_query() {
+    let injected = Query::new()
+        .block(Block::root("q", Func::has("name) { uid } me(func: has(secret")).field("uid"))
+        .build();
+
+    match injected {
+        Err(DgraphError::InvalidDql(_)) => (),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn it_rejects_aliases_on_root_blocks() {
+    let aliased = Query::new()
+        .block(
+            Block::root("q", Func::has("name"))
+                .alias("people")
+                .field("uid"),
+        )
+        .build();
+
+    match aliased {
+        Err(DgraphError::InvalidDql(_)) => (),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn it_rejects_empty_filter_groups() {
+    for filter in vec![Filter::And(vec![]), Filter::Or(vec![])] {
+        let empty = Query::new()
+            .block(
+                Block::root("q", Func::has("name"))
+                    .filter(filter)
+                    .field("uid"),
+            )
+            .build();
+
+        match empty {
+            Err(DgraphError::InvalidDql(_)) => (),
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+}
+
+#[test]
+fn it_runs_built_queries() {
+    let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
+
+    let (query, vars) = Query::new()
+        .block(Block::root("uids", Func::uid(Some(Uid(1)))).field("uid"))
+        .build()
+        .unwrap();
+
+    let uids: Vec<HashMap<String, Uid>> = dgraph
+        .new_readonly_txn()
+        .unwrap()
+        .query_with_vars(&query, vars)
+        .unwrap()
+        .block_as("uids")
+        .unwrap();
+
+    assert_eq!(uids[0]["uid"], Uid(1));
+}
@@ -0,0 +1,116 @@
+use dgraph::export::Exporter;
+use dgraph::loader::Format;
+use dgraph::{make_dgraph, rdf, Dgraph};
+use flate2::read::GzDecoder;
+use serde_json::json;
+use std::collections::HashSet;
+use std::io::Read;
+
+mod common;
+
+/// Creates `count` people of a type of their own, so every run exports only its own nodes.
+fn setup(count: usize) -> (Dgraph, String) {
+    let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
+    let type_name = format!("ExportPerson{}", std::process::id());
+    dgraph
+        .alter(&dgraph::Operation {
+            schema: format!(
+                "Export.name: string @index(exact) .\n\
+                 Export.age: int .\n\
+                 Export.friend: [uid] .\n\
+                 type {} {{ Export.name Export.age Export.friend }}",
+                type_name
+            ),
+            ..Default::default()
+        })
+        .unwrap();
+
+    let people: Vec<_> = (0..count)
+        .map(|index| {
+            json!({
+                "uid": format!("_:p{}", index),
+                "dgraph.type": type_name,
+                "Export.name": format!("person {}", index),
+                "Export.age": 20 + index,
+                "Export.friend": [{ "uid": format!("_:p{}", (index + 1) % count) }],
+            })
+        })
+        .collect();
+    dgraph
+        .new_txn()
+        .unwrap()
+        .set_json_commit_now(&people)
+        .unwrap();
+
+    (dgraph, type_name)
+}
+
+#[test]
+fn it_exports_every_node_of_a_type_as_ndjson_across_pages() {
+    let (dgraph, type_name) = setup(5);
+
+    let mut out = Vec::new();
+    let nodes = Exporter::of_type(type_name.as_str())
+        .page_size(2)
+        .export_to(&dgraph, &mut out)
+        .unwrap();
+
+    assert_eq!(nodes, 5);
+    let lines: Vec<serde_json::Value> = String::from_utf8(out)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 5);
+
+    let uids: HashSet<&str> = lines
+        .iter()
+        .map(|node| node["uid"].as_str().unwrap())
+        .collect();
+    assert_eq!(uids.len(), 5);
+    for node in &lines {
+        assert!(node["Export.name"].as_str().unwrap().starts_with("person "));
+        assert_eq!(node["dgraph.type"], json!([type_name]));
+    }
+}
+
+#[test]
+fn it_exports_gzipped_rdf_that_parses_back() {
+    let (dgraph, type_name) = setup(3);
+
+    let mut out = Vec::new();
+    let nodes = Exporter::of_type(type_name.as_str())
+        .page_size(2)
+        .format(Format::Rdf)
+        .gzip(true)
+        .export_to(&dgraph, &mut out)
+        .unwrap();
+    assert_eq!(nodes, 3);
+
+    let mut text = String::new();
+    GzDecoder::new(out.as_slice())
+        .read_to_string(&mut text)
+        .unwrap();
+    let nquads = rdf::parse(&text).unwrap();
+
+    let subjects: HashSet<&str> = nquads.iter().map(|nquad| nquad.subject.as_str()).collect();
+    assert_eq!(subjects.len(), 3);
+
+    let count = |predicate: &str| {
+        nquads
+            .iter()
+            .filter(|nquad| nquad.predicate == predicate)
+            .count()
+    };
+    assert_eq!(count("dgraph.type"), 3);
+    assert_eq!(count("Export.name"), 3);
+    assert_eq!(count("Export.age"), 3);
+    assert_eq!(count("Export.friend"), 3);
+
+    for nquad in nquads
+        .iter()
+        .filter(|nquad| nquad.predicate == "Export.friend")
+    {
+        assert!(subjects.contains(nquad.object_id.as_str()));
+    }
+}
@@ -0,0 +1,167 @@
+use dgraph::loader::{Format, Loader};
+use dgraph::{make_dgraph, Dgraph, DgraphError};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde_derive::Deserialize;
+use std::io::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+mod common;
+
+#[derive(Deserialize, Debug)]
+struct Member {
+    #[serde(rename = "Loader.name")]
+    name: String,
+    #[serde(rename = "Loader.team", default)]
+    team: Vec<Team>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Team {
+    uid: String,
+}
+
+fn setup() -> Dgraph {
+    let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
+    dgraph
+        .alter(&dgraph::Operation {
+            schema: "Loader.name: string @index(exact) .\n\
+                     Loader.team: [uid] ."
+                .to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+    dgraph
+}
+
+fn members(dgraph: &Dgraph, names: &[&str]) -> Vec<Member> {
+    let names: Vec<String> = names.iter().map(|name| format!("{:?}", name)).collect();
+    let query = format!(
+        "{{ members(func: eq(Loader.name, [{}])) {{ Loader.name Loader.team {{ uid }} }} }}",
+        names.join(", ")
+    );
+
+    dgraph
+        .new_readonly_txn()
+        .unwrap()
+        .query(&query)
+        .unwrap()
+        .block_as("members")
+        .unwrap()
+}
+
+#[test]
+fn it_guesses_formats_from_file_names() {
+    assert_eq!(Format::from_path("data.rdf.gz"), Some(Format::Rdf));
+    assert_eq!(Format::from_path("dir/data.nq"), Some(Format::Rdf));
+    assert_eq!(Format::from_path("people.json"), Some(Format::Json));
+    assert_eq!(Format::from_path("people.ndjson.gz"), Some(Format::Json));
+    assert_eq!(Format::from_path("notes.txt"), None);
+}
+
+#[test]
+fn it_loads_gzipped_rdf_keeping_blank_nodes_across_batches() {
+    let dgraph = setup();
+
+    let rdf = r#"
+        _:team <Loader.name> "Loader team" .
+        _:ann <Loader.name> "Loader Ann" .
+        _:ann <Loader.team> _:team .
+        _:ben <Loader.name> "Loader Ben" .
+        _:ben <Loader.team> _:team .
+        _:cid <Loader.name> "Loader Cid" .
+        _:cid <Loader.team> _:team .
+    "#;
+    let mut gzip = GzEncoder::new(Vec::new(), Compression::default());
+    gzip.write_all(rdf.as_bytes()).unwrap();
+    let input = gzip.finish().unwrap();
+
+    let calls = Arc::new(AtomicU64::new(0));
+    let progress = {
+        let calls = Arc::clone(&calls);
+        Loader::new()
+            .batch_size(2)
+            .concurrency(3)
+            .on_progress(move |_| {
+                calls.fetch_add(1, Ordering::SeqCst);
+            })
+            .load(&dgraph, &input[..], Format::Rdf)
+            .unwrap()
+    };
+
+    assert_eq!(progress.records, 7);
+    assert_eq!(progress.batches, 4);
+    assert_eq!(calls.load(Ordering::SeqCst), 4);
+
+    let members = members(&dgraph, &["Loader Ann", "Loader Ben", "Loader Cid"]);
+    assert_eq!(members.len(), 3);
+    let team = &members[0].team[0].uid;
+    assert!(members.iter().all(|member| &member.team[0].uid == team));
+}
+
+#[test]
+fn it_loads_json_arrays_and_lines() {
+    let dgraph = setup();
+
+    let json = r#"
+        [{"uid": "_:dee", "Loader.name": "Loader Dee"}, {"Loader.name": "Loader Eve"}]
+        {"Loader.name": "Loader Fay", "Loader.team": [{"uid": "_:dee"}]}
+    "#;
+
+    let progress = Loader::new()
+        .batch_size(1)
+        .load(&dgraph, json.as_bytes(), Format::Json)
+        .unwrap();
+    assert_eq!(progress.records, 3);
+
+    let members = members(&dgraph, &["Loader Fay"]);
+    let dee = uid_of(&dgraph, "Loader Dee");
+    assert_eq!(members[0].name, "Loader Fay");
+    assert_eq!(members[0].team[0].uid, dee);
+}
+
+fn uid_of(dgraph: &Dgraph, name: &str) -> String {
+    let query = format!("{{ m(func: eq(Loader.name, {:?})) {{ uid }} }}", name);
+    let uids: Vec<Team> = dgraph
+        .new_readonly_txn()
+        .unwrap()
+        .query(&query)
+        .unwrap()
+        .block_as("m")
+        .unwrap();
+    uids[0].uid.clone()
+}
+
+#[test]
+fn it_reports_the_line_of_rdf_syntax_errors() {
+    let dgraph = setup();
+    let rdf = "_:a <Loader.name> \"ok\" .\n_:b <Loader.name> \"broken .\n";
+
+    match Loader::new().load(&dgraph, rdf.as_bytes(), Format::Rdf) {
+        Err(DgraphError::RdfSyntax { line, column, .. }) => assert_eq!((line, column), (2, 19)),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn it_returns_an_error_when_a_worker_panics() {
+    let dgraph = setup();
+    let rdf = r#"
+        _:gus <Loader.name> "Loader Gus" .
+        _:hal <Loader.name> "Loader Hal" .
+        _:hal <Loader.team> _:gus .
+        _:ida <Loader.team> _:gus .
+    "#;
+
+    let result = Loader::new()
+        .batch_size(1)
+        .concurrency(1)
+        .on_progress(|_| panic!("progress callback failed"))
+        .load(&dgraph, rdf.as_bytes(), Format::Rdf);
+
+    match result {
+        Err(DgraphError::LoaderPanicked) => (),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
@@ -0,0 +1,113 @@
+use std::fs;
+
+use dgraph::migrate::{Migration, Migrator};
+use dgraph::{make_dgraph, Dgraph, DgraphError, Upsert};
+use serde_json::json;
+
+mod common;
+
+/// Forgets applied migrations and any lock left over from earlier runs.
+fn reset(dgraph: &Dgraph) {
+    let upsert = Upsert::new(
+        "{ m as var(func: type(DgraphMigration)) l as var(func: type(DgraphMigrationLock)) }",
+    )
+    .delete_nquads("uid(m) * * .\nuid(l) * * .")
+    .commit_now();
+
+    dgraph.new_txn().unwrap().upsert(upsert).unwrap();
+}
+
+fn migrator() -> Migrator {
+    Migrator::new()
+        .migration(
+            Migration::new(1, "create migrated people")
+                .schema("MigratedPerson.name: string @index(exact) ."),
+        )
+        .migration(Migration::new(2, "add admin").data(|txn| {
+            txn.set_json(&json!({ "MigratedPerson.name": "admin" }))?;
+            Ok(())
+        }))
+}
+
+// Runs as one test since the migrations and the lock are shared by the whole database.
+#[test]
+fn it_applies_migrations_once_under_a_lock() {
+    let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
+    reset(&dgraph);
+
+    assert_eq!(migrator().pending(&dgraph).unwrap().len(), 2);
+    assert_eq!(migrator().run(&dgraph).unwrap(), vec![1, 2]);
+    assert_eq!(migrator().run(&dgraph).unwrap(), Vec::<u64>::new());
+
+    let applied = migrator().applied(&dgraph).unwrap();
+    assert_eq!(
+        applied
+            .iter()
+            .map(|migration| (migration.version, migration.name.as_str()))
+            .collect::<Vec<_>>(),
+        vec![(1, "create migrated people"), (2, "add admin")]
+    );
+
+    let renamed = Migrator::new().migration(Migration::new(1, "renamed"));
+    match renamed.run(&dgraph) {
+        Err(DgraphError::Migration(_)) => (),
+        other => panic!("expected a migration error, got {:?}", other),
+    }
+
+    let mut txn = dgraph.new_txn().unwrap();
+    txn.set_json(&json!({
+        "dgraph.type": "DgraphMigrationLock",
+        "DgraphMigrationLock.name": "migrations",
+        "DgraphMigrationLock.owner": "someone-else",
+        "DgraphMigrationLock.acquiredAt": "2100-01-01T00:00:00Z",
+    }))
+    .unwrap();
+    txn.commit().unwrap();
+
+    match migrator().run(&dgraph) {
+        Err(DgraphError::MigrationLocked(owner)) => assert_eq!(owner, "someone-else"),
+        other => panic!("expected the lock to be held, got {:?}", other),
+    }
+
+    reset(&dgraph);
+    let taken_over = Migrator::new()
+        .migration(Migration::new(1, "take over the lock").data(|txn| {
+            let upsert = Upsert::new("{ l as var(func: type(DgraphMigrationLock)) }")
+                .set_nquads(r#"uid(l) <DgraphMigrationLock.owner> "someone-else" ."#);
+            txn.upsert(upsert)?;
+            Ok(())
+        }))
+        .migration(Migration::new(2, "add admin"));
+    match taken_over.run(&dgraph) {
+        Err(DgraphError::MigrationLocked(owner)) => assert_eq!(owner, "someone-else"),
+        other => panic!("expected the lock to be lost, got {:?}", other),
+    }
+    let applied = taken_over.applied(&dgraph).unwrap();
+    assert_eq!(applied.len(), 1);
+    assert_eq!(applied[0].version, 1);
+
+    reset(&dgraph);
+}
+
+#[test]
+fn it_loads_migrations_from_a_directory() {
+    let dir = std::env::temp_dir().join(format!("dgraph-migrations-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("0001_people.schema"), "name: string .").unwrap();
+    fs::write(dir.join("0002_admin.rdf"), "_:a <name> \"admin\" .").unwrap();
+    fs::write(dir.join("0002_admin.json"), "{\"name\": \"root\"}").unwrap();
+    fs::write(dir.join("README.md"), "not a migration").unwrap();
+
+    let migrator = Migrator::from_dir(&dir).unwrap();
+    let migrations = migrator
+        .migrations()
+        .iter()
+        .map(|migration| (migration.version(), migration.name()))
+        .collect::<Vec<_>>();
+    assert_eq!(migrations, vec![(1, "people"), (2, "admin")]);
+
+    fs::write(dir.join("0002_other.json"), "{}").unwrap();
+    assert!(Migrator::from_dir(&dir).is_err());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,138 @@
+use dgraph::{make_dgraph, DgraphNode, Uid};
+use serde_derive::{Deserialize, Serialize};
+use serde_json::json;
+
+mod common;
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+struct Location {
+    #[serde(rename = "type")]
+    kind: String,
+    coordinates: Vec<f64>,
+}
+
+#[derive(DgraphNode, Deserialize, Default, Debug)]
+struct School {
+    uid: Option<Uid>,
+    #[dgraph(predicate = "School.name", index(exact))]
+    name: String,
+}
+
+#[derive(DgraphNode, Deserialize, Default, Debug)]
+#[dgraph(name = "Person")]
+struct Person {
+    #[dgraph(uid)]
+    id: Option<Uid>,
+    #[dgraph(predicate = "Person.name", index(exact, term))]
+    name: String,
+    age: Option<u8>,
+    #[dgraph(schema_type = "geo")]
+    loc: Option<Location>,
+    #[dgraph(edge, reverse, count)]
+    friend: Option<Vec<Person>>,
+    #[dgraph(edge)]
+    school: Option<School>,
+    #[dgraph(skip)]
+    #[serde(skip)]
+    scratch: Option<String>,
+}
+
+#[test]
+fn it_derives_the_schema() {
+    assert_eq!(Person::TYPE_NAME, "Person");
+    assert_eq!(
+        Person::schema(),
+        "Person.name: string @index(exact, term) .\n\
+         age: int .\n\
+         loc: geo .\n\
+         friend: [uid] @reverse @count .\n\
+         school: uid .\n\
+         type Person {\n  Person.name\n  age\n  loc\n  friend\n  school\n}\n"
+    );
+}
+
+#[test]
+fn it_derives_a_selection_set() {
+    assert_eq!(School::selection(), "{ uid name: School.name }");
+    assert_eq!(
+        Person::selection_to_depth(1),
+        "{ id: uid name: Person.name age loc \
+         friend { id: uid name: Person.name age loc } \
+         school { uid name: School.name } }"
+    );
+}
+
+#[test]
+fn it_derives_mutation_json() {
+    let alice = Person {
+        name: "Alice".to_string(),
+        friend: Some(vec![Person {
+            id: Some(Uid(0x2a)),
+            name: "Bob".to_string(),
+            ..Default::default()
+        }]),
+        scratch: Some("ignored".to_string()),
+        ..Default::default()
+    };
+
+    assert_eq!(
+        alice.to_mutation_json().unwrap(),
+        json!({
+            "dgraph.type": "Person",
+            "Person.name": "Alice",
+            "friend": [{
+                "dgraph.type": "Person",
+                "uid": "0x2a",
+                "Person.name": "Bob",
+            }],
+        })
+    );
+}
+
+#[test]
+fn it_stores_and_queries_derived_nodes() {
+    let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
+
+    dgraph
+        .alter(&dgraph::Operation {
+            schema: format!("{}{}", School::schema(), Person::schema()),
+            ..Default::default()
+        })
+        .unwrap();
+
+    let alice = Person {
+        name: "Alice".to_string(),
+        age: Some(26),
+        school: Some(School {
+            name: "Crown Public School".to_string(),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    dgraph
+        .new_txn()
+        .unwrap()
+        .set_json_commit_now(&alice.to_mutation_json().unwrap())
+        .unwrap();
+
+    let query = format!(
+        r#"query all($a: string) {{
+            people(func: eq(Person.name, $a)) @filter(type(Person)) {}
+        }}"#,
+        Person::selection()
+    );
+    let people: Vec<Person> = dgraph
+        .new_readonly_txn()
+        .unwrap()
+        .query_with_vars(&query, dgraph::Vars::new().string("$a", "Alice"))
+        .unwrap()
+        .block_as("people")
+        .unwrap();
+
+    assert!(people[0].id.is_some());
+    assert_eq!(people[0].age, Some(26));
+    assert_eq!(
+        people[0].school.as_ref().unwrap().name,
+        "Crown Public School"
+    );
+}
@@ -0,0 +1,131 @@
+use dgraph::{make_dgraph, Facet, Facet_ValType, Mutation, NQuad, Uid, Value, Value_oneof_val};
+use serde_json::json;
+use std::time::{Duration, UNIX_EPOCH};
+
+mod common;
+
+#[test]
+fn it_builds_literals_and_edges() {
+    let name = NQuad::literal("_:alice", "name", "Alicia").lang("es");
+    assert_eq!(name.subject, "_:alice");
+    assert_eq!(name.predicate, "name");
+    assert_eq!(name.lang, "es");
+    assert_eq!(
+        name.get_object_value().val,
+        Some(Value_oneof_val::str_val("Alicia".to_string()))
+    );
+
+    let friend = NQuad::edge("_:alice", "friend", Uid(0x2a));
+    assert_eq!(friend.object_id, "0x2a");
+    assert!(!friend.has_object_value());
+}
+
+#[test]
+fn it_encodes_datetimes_like_go() {
+    let value = Value::datetime(UNIX_EPOCH + Duration::new(1, 5));
+
+    assert_eq!(
+        value.val,
+        Some(Value_oneof_val::datetime_val(vec![
+            1, 0, 0, 0, 0x0e, 0x77, 0x91, 0xf7, 0x01, 0, 0, 0, 5, 0xff, 0xff
+        ]))
+    );
+}
+
+#[test]
+fn it_encodes_geo_values_as_wkb() {
+    let value = Value::geo_point([1.5, 0.0]);
+
+    assert_eq!(
+        value.val,
+        Some(Value_oneof_val::geo_val(vec![
+            1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xf8, 0x3f, 0, 0, 0, 0, 0, 0, 0, 0
+        ]))
+    );
+
+    match Value::geo_polygon(vec![vec![[0.0, 0.0], [1.0, 0.0], [0.0, 1.0], [0.0, 0.0]]]).val {
+        Some(Value_oneof_val::geo_val(wkb)) => {
+            assert_eq!(&wkb[..13], &[1, 3, 0, 0, 0, 1, 0, 0, 0, 4, 0, 0, 0]);
+            assert_eq!(wkb.len(), 13 + 4 * 16);
+        }
+        val => panic!("unexpected value: {:?}", val),
+    }
+}
+
+#[test]
+fn it_encodes_facets() {
+    let facet = Facet::with_value("weight", 1.5);
+    assert_eq!(facet.val_type, Facet_ValType::FLOAT);
+    assert_eq!(facet.value, vec![0, 0, 0, 0, 0, 0, 0xf8, 0x3f]);
+
+    let facet = Facet::with_value("close", true);
+    assert_eq!(facet.val_type, Facet_ValType::BOOL);
+    assert_eq!(facet.value, vec![1]);
+
+    let facet = Facet::with_value("since", UNIX_EPOCH);
+    assert_eq!(facet.val_type, Facet_ValType::DATETIME);
+    assert_eq!(facet.value.len(), 15);
+
+    let facet = Facet::with_value("note", "Met at School, school trip");
+    assert_eq!(facet.val_type, Facet_ValType::STRING);
+    assert_eq!(facet.value, b"Met at School, school trip".to_vec());
+    assert_eq!(
+        facet.tokens.to_vec(),
+        vec!["\u{1}at", "\u{1}met", "\u{1}school", "\u{1}trip"]
+    );
+}
+
+#[test]
+fn it_mutates_with_nquads() {
+    let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
+
+    dgraph
+        .alter(&dgraph::Operation {
+            schema: "NQuad.name: string @index(exact) @lang .\n\
+                     NQuad.born: datetime .\n\
+                     NQuad.friend: [uid] ."
+                .to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+
+    let mut mu = Mutation::new();
+    mu.set
+        .push(NQuad::literal("_:alice", "NQuad.name", "Alice"));
+    mu.set
+        .push(NQuad::literal("_:alice", "NQuad.name", "Alicia").lang("es"));
+    mu.set.push(NQuad::literal(
+        "_:alice",
+        "NQuad.born",
+        UNIX_EPOCH + Duration::from_secs(86_400),
+    ));
+    mu.set.push(NQuad::literal("_:bob", "NQuad.name", "Bob"));
+    mu.set
+        .push(NQuad::edge("_:alice", "NQuad.friend", "_:bob").facet("close", true));
+    mu.set_commit_now(true);
+
+    let mut txn = dgraph.new_txn().unwrap();
+    let assigned = txn.mutate(mu).unwrap();
+    let alice = assigned.uids["alice"].clone();
+
+    let response = dgraph
+        .new_readonly_txn()
+        .unwrap()
+        .query(&format!(
+            "{{ me(func: uid({})) {{ NQuad.name NQuad.name@es NQuad.born \
+             NQuad.friend @facets(close) {{ NQuad.name }} }} }}",
+            alice
+        ))
+        .unwrap();
+    let me: serde_json::Value = response.block_as("me").unwrap();
+
+    assert_eq!(
+        me,
+        json!([{
+            "NQuad.name": "Alice",
+            "NQuad.name@es": "Alicia",
+            "NQuad.born": "1970-01-02T00:00:00Z",
+            "NQuad.friend": [{ "NQuad.name": "Bob", "NQuad.friend|close": true }],
+        }])
+    );
+}
@@ -0,0 +1,148 @@
+use dgraph::{rdf, DgraphError, Facet_ValType, NQuad, Value, Value_oneof_val};
+use std::time::{Duration, UNIX_EPOCH};
+
+#[test]
+fn it_parses_nodes_literals_and_facets() {
+    let nquads = rdf::parse(
+        r#"
+        # people
+        _:alice <name> "Alice" .
+        _:alice <name> "Alicia"@es .
+        <0x1a> <age> "26"^^<xs:int> <import> .
+        _:alice <friend> uid(bob) (since=2006-01-02T15:04:05, weight=0.5, close=true, note="met \"there\"") .
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(nquads.len(), 4);
+    assert_eq!(
+        nquads[0],
+        NQuad::literal(
+            "_:alice",
+            "name",
+            Value {
+                val: Some(Value_oneof_val::default_val("Alice".to_string())),
+                ..Default::default()
+            }
+        )
+    );
+    assert_eq!(nquads[1].lang, "es");
+
+    assert_eq!(nquads[2].subject, "0x1a");
+    assert_eq!(nquads[2].label, "import");
+    assert_eq!(
+        nquads[2].get_object_value().val,
+        Some(Value_oneof_val::int_val(26))
+    );
+
+    let friend = &nquads[3];
+    assert_eq!(friend.object_id, "uid(bob)");
+    let kinds: Vec<_> = friend
+        .facets
+        .iter()
+        .map(|facet| (facet.key.as_str(), facet.val_type))
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![
+            ("since", Facet_ValType::DATETIME),
+            ("weight", Facet_ValType::FLOAT),
+            ("close", Facet_ValType::BOOL),
+            ("note", Facet_ValType::STRING),
+        ]
+    );
+    assert_eq!(friend.facets[3].value, b"met \"there\"".to_vec());
+}
+
+#[test]
+fn it_parses_typed_literals() {
+    let nquads = rdf::parse(
+        r#"_:a <born> "1970-01-02T00:00:01.5Z"^^<xs:dateTime> .
+           _:a <home> "{\"type\":\"Point\",\"coordinates\":[1.5,0]}"^^<geo:geojson> .
+           _:a <secret> "hunter2"^^<xs:password> .
+           _:a <admin> "true"^^<http://www.w3.org/2001/XMLSchema#boolean> ."#,
+    )
+    .unwrap();
+
+    let values: Vec<_> = nquads
+        .iter()
+        .map(|nquad| nquad.get_object_value().clone())
+        .collect();
+    assert_eq!(
+        values,
+        vec![
+            Value::datetime(UNIX_EPOCH + Duration::from_millis(86_401_500)),
+            Value::geo_point([1.5, 0.0]),
+            Value::password("hunter2"),
+            Value::bool(true),
+        ]
+    );
+}
+
+#[test]
+fn it_parses_star_deletes() {
+    let nquads = rdf::parse("<0x1> * * .\n<0x1> <name> * .").unwrap();
+
+    assert_eq!(nquads[0].predicate, rdf::STAR_ALL);
+    assert_eq!(
+        nquads[1].get_object_value().val,
+        Some(Value_oneof_val::default_val(rdf::STAR_ALL.to_string()))
+    );
+    assert_eq!(rdf::to_string(&nquads), "<0x1> * * .\n<0x1> <name> * .\n");
+}
+
+#[test]
+fn it_reports_error_positions() {
+    let errors = vec![
+        ("_:a <name> \"Alice\"", (1, 19)),
+        ("_:a <name> \"Alice\" .\n_:a name \"Bob\" .", (2, 5)),
+        ("_:a <age> \"x\"^^<xs:int> .", (1, 16)),
+        ("_:a <age> \"1\"^^<xs:unknown> .", (1, 16)),
+        ("_:a <p> _:b (weight=heavy) .", (1, 21)),
+        ("_:a <p> \"open .", (1, 9)),
+    ];
+
+    for (text, position) in errors {
+        match rdf::parse(text) {
+            Err(DgraphError::RdfSyntax { line, column, .. }) => {
+                assert_eq!((line, column), position, "in {:?}", text)
+            }
+            result => panic!("unexpected result for {:?}: {:?}", text, result),
+        }
+    }
+}
+
+#[test]
+fn it_round_trips_nquads() {
+    let nquads = vec![
+        NQuad::literal("_:alice", "name", "Alice \"A\"\n"),
+        NQuad::literal("_:alice", "age", 26i64),
+        NQuad::literal("_:alice", "score", 1.0),
+        NQuad::literal("_:alice", "born", UNIX_EPOCH + Duration::from_secs(86_400)),
+        NQuad::literal(
+            "_:alice",
+            "area",
+            Value::geo_polygon(vec![vec![[0.0, 0.0], [1.0, 0.0], [0.0, 1.0], [0.0, 0.0]]]),
+        ),
+        NQuad::edge("_:alice", "friend", "0x2a")
+            .facet("since", UNIX_EPOCH)
+            .facet("count", 3i64)
+            .facet("weight", 2.0)
+            .facet("close", false)
+            .facet("note", "old friend"),
+    ];
+
+    let text = rdf::to_string(&nquads);
+    assert!(text.starts_with(
+        "_:alice <name> \"Alice \\\"A\\\"\\n\"^^<xs:string> .\n\
+         _:alice <age> \"26\"^^<xs:int> .\n\
+         _:alice <score> \"1.0\"^^<xs:float> .\n\
+         _:alice <born> \"1970-01-02T00:00:00Z\"^^<xs:dateTime> .\n"
+    ));
+    assert!(text.ends_with(
+        "_:alice <friend> <0x2a> (since=1970-01-01T00:00:00Z, count=3, weight=2.0, close=false, \
+         note=\"old friend\") .\n"
+    ));
+
+    assert_eq!(rdf::parse(&text).unwrap(), nquads);
+}
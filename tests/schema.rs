@@ -0,0 +1,213 @@
+use dgraph::schema::{self, Predicate, ScalarType, Schema, Tokenizer, TypeDef};
+use dgraph::{make_dgraph, DgraphError};
+
+mod common;
+
+fn person_schema() -> Schema {
+    Schema::new()
+        .predicate(
+            Predicate::new("name", ScalarType::String)
+                .index(vec![Tokenizer::Exact, Tokenizer::Term])
+                .upsert()
+                .lang(),
+        )
+        .predicate(
+            Predicate::new("nickname", ScalarType::String)
+                .list()
+                .no_conflict(),
+        )
+        .predicate(Predicate::new("dob", ScalarType::DateTime).index(vec![Tokenizer::Year]))
+        .predicate(
+            Predicate::new("friend", ScalarType::Uid)
+                .list()
+                .reverse()
+                .count(),
+        )
+        .type_def(
+            TypeDef::new("Person")
+                .field("name")
+                .field("nickname")
+                .field("dob")
+                .field("friend")
+                .field("~friend"),
+        )
+}
+
+#[test]
+fn it_renders_schema_text() {
+    assert_eq!(
+        person_schema().to_string(),
+        "name: string @index(exact, term) @upsert @lang .\n\
+         nickname: [string] @noconflict .\n\
+         dob: datetime @index(year) .\n\
+         friend: [uid] @reverse @count .\n\
+         \n\
+         type Person {\n  name\n  nickname\n  dob\n  friend\n  <~friend>\n}\n"
+    );
+}
+
+#[test]
+fn it_parses_rendered_schema_back() {
+    let schema = person_schema();
+    let parsed: Schema = schema.to_string().parse().unwrap();
+
+    assert_eq!(parsed, schema);
+}
+
+#[test]
+fn it_parses_hand_written_schema() {
+    let parsed: Schema = r#"
+        # People and their friends.
+        <name>: string @index(exact).
+        age: int .
+        type Person {
+            name: string
+            age: int
+        }
+    "#
+    .parse()
+    .unwrap();
+
+    assert_eq!(
+        parsed,
+        Schema::new()
+            .predicate(Predicate::new("name", ScalarType::String).index(vec![Tokenizer::Exact]))
+            .predicate(Predicate::new("age", ScalarType::Int))
+            .type_def(TypeDef::new("Person").field("name").field("age"))
+    );
+}
+
+#[test]
+fn it_reports_where_schema_text_is_invalid() {
+    match "name: string .\nage: integer .".parse::<Schema>() {
+        Err(DgraphError::SchemaSyntax { line, column, .. }) => assert_eq!((line, column), (2, 6)),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn it_alters_with_a_generated_schema() {
+    let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
+
+    let schema = Schema::new()
+        .predicate(Predicate::new("nickname", ScalarType::String).list())
+        .type_def(TypeDef::new("Nicknamed").field("nickname"));
+
+    dgraph.alter(&schema.to_operation()).unwrap();
+}
+
+#[test]
+fn it_diffs_schemas_into_operations() {
+    let current = person_schema();
+    let desired = Schema::new()
+        .predicate(
+            Predicate::new("name", ScalarType::String)
+                .index(vec![Tokenizer::Term, Tokenizer::Trigram])
+                .upsert()
+                .lang(),
+        )
+        .predicate(Predicate::new("dob", ScalarType::DateTime).index(vec![Tokenizer::Year]))
+        .predicate(
+            Predicate::new("friend", ScalarType::Uid)
+                .list()
+                .reverse()
+                .count(),
+        )
+        .predicate(Predicate::new("age", ScalarType::Int))
+        .type_def(
+            TypeDef::new("Person")
+                .field("~friend")
+                .field("friend")
+                .field("dob")
+                .field("name")
+                .field("age"),
+        );
+
+    let diff = schema::diff(&current, &desired);
+
+    assert_eq!(
+        diff.added_predicates,
+        vec![Predicate::new("age", ScalarType::Int)]
+    );
+    assert_eq!(diff.removed_predicates.len(), 1);
+    assert_eq!(diff.removed_predicates[0].name, "nickname");
+    assert_eq!(diff.changed_predicates.len(), 1);
+    assert_eq!(
+        diff.changed_predicates[0].added_tokenizers(),
+        vec![Tokenizer::Trigram]
+    );
+    assert_eq!(
+        diff.changed_predicates[0].removed_tokenizers(),
+        vec![Tokenizer::Exact]
+    );
+    assert_eq!(
+        diff.changed_types[0].added_fields(),
+        vec!["age".to_string()]
+    );
+    assert_eq!(
+        diff.changed_types[0].removed_fields(),
+        vec!["nickname".to_string()]
+    );
+
+    let operations = diff.to_operations(false);
+    assert_eq!(operations.len(), 1);
+    assert_eq!(operations[0].drop_op, dgraph::Operation_DropOp::NONE);
+
+    let operations = diff.to_operations(true);
+    assert_eq!(operations.len(), 2);
+    assert_eq!(
+        operations[0].schema,
+        "age: int .\n\
+         name: string @index(term, trigram) @upsert @lang .\n\
+         \n\
+         type Person {\n  <~friend>\n  friend\n  dob\n  name\n  age\n}\n"
+    );
+    assert_eq!(operations[1].drop_op, dgraph::Operation_DropOp::ATTR);
+    assert_eq!(operations[1].drop_value, "nickname");
+
+    assert!(schema::diff(&desired, &desired).is_empty());
+}
+
+#[test]
+fn it_reads_the_current_schema_and_applies_the_diff() {
+    let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
+
+    let desired = Schema::new()
+        .predicate(
+            Predicate::new("Migrated.title", ScalarType::String).index(vec![Tokenizer::Hash]),
+        )
+        .predicate(
+            Predicate::new("Migrated.tags", ScalarType::String)
+                .list()
+                .no_conflict(),
+        )
+        .type_def(
+            TypeDef::new("Migrated")
+                .field("Migrated.title")
+                .field("Migrated.tags"),
+        );
+
+    let diff = schema::diff(&dgraph.current_schema().unwrap(), &desired);
+    for operation in diff.to_operations(false) {
+        dgraph.alter(&operation).unwrap();
+    }
+
+    let current = dgraph.current_schema().unwrap();
+    assert_eq!(
+        current.get_predicate("Migrated.title"),
+        desired.get_predicate("Migrated.title")
+    );
+    assert_eq!(
+        current.get_predicate("Migrated.tags"),
+        desired.get_predicate("Migrated.tags")
+    );
+    let mut fields = current.get_type("Migrated").unwrap().fields.clone();
+    fields.sort();
+    assert_eq!(fields, vec!["Migrated.tags", "Migrated.title"]);
+
+    let remaining = schema::diff(&current, &desired);
+    assert!(remaining.added_predicates.is_empty());
+    assert!(remaining.changed_predicates.is_empty());
+    assert!(remaining.added_types.is_empty());
+    assert!(remaining.changed_types.is_empty());
+}
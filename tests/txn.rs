@@ -30,7 +30,7 @@ fn it_runs_simple_query() {
         }}"#,
         uid
     );
-    let resp = dgraph.new_readonly_txn().query(&query);
+    let resp = dgraph.new_readonly_txn().unwrap().query(&query);
     let json: UidJson = serde_json::from_slice(&resp.unwrap().json).unwrap();
 
     assert_eq!(json.uids[0].uid, uid);
@@ -49,7 +49,10 @@ fn it_runs_query_with_vars() {
     .to_string();
     let mut vars = HashMap::new();
     vars.insert("$a".to_string(), uid.to_string());
-    let resp = dgraph.new_readonly_txn().query_with_vars(&query, vars);
+    let resp = dgraph
+        .new_readonly_txn()
+        .unwrap()
+        .query_with_vars(&query, vars);
     let json: UidJson = serde_json::from_slice(&resp.unwrap().json).unwrap();
 
     assert_eq!(json.uids[0].uid, uid);
@@ -65,8 +68,11 @@ fn it_returns_error_if_mandatory_var_is_omitted() {
         }
     }"#
     .to_string();
-    let vars = HashMap::new();
-    let resp = dgraph.new_readonly_txn().query_with_vars(&query, vars);
+    let vars: HashMap<String, String> = HashMap::new();
+    let resp = dgraph
+        .new_readonly_txn()
+        .unwrap()
+        .query_with_vars(&query, vars);
 
     let error_matched = match resp.unwrap_err() {
         DgraphError::GrpcError(grpcio::Error::RpcFailure(_)) => true,
@@ -75,6 +81,71 @@ fn it_returns_error_if_mandatory_var_is_omitted() {
     assert!(error_matched);
 }
 
+#[test]
+fn it_runs_query_with_typed_vars() {
+    let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
+
+    let query = r#"query all($a: string, $first: int){
+        uids(func: uid($a), first: $first) {
+            uid,
+        }
+    }"#;
+    let vars = dgraph::Vars::new().uid("a", 1).int("$first", 1);
+    let json: UidJson = dgraph
+        .new_readonly_txn()
+        .unwrap()
+        .query_with_vars_as(query, vars)
+        .unwrap();
+
+    assert_eq!(json.uids[0].uid, "0x1");
+}
+
+#[test]
+fn it_rejects_vars_missing_from_the_query_header() {
+    let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
+
+    let query = r#"query all($a: int){
+        uids(func: uid(0x1)) {
+            uid
+        }
+    }"#;
+    let mut txn = dgraph.new_readonly_txn().unwrap();
+
+    let undeclared = txn.query_with_vars(query, dgraph::Vars::new().int("$b", 1));
+    let mistyped = txn.query_with_vars(query, dgraph::Vars::new().string("$a", "x"));
+
+    match (undeclared.unwrap_err(), mistyped.unwrap_err()) {
+        (DgraphError::InvalidVars(_), DgraphError::InvalidVars(_)) => (),
+        errors => panic!("unexpected errors: {:?}", errors),
+    }
+}
+
+#[test]
+fn it_passes_untyped_vars_through_unchecked() {
+    let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
+
+    let mut vars = HashMap::new();
+    vars.insert("$a".to_string(), "0x1".to_string());
+
+    let typed_vars = dgraph::Vars::from(vars.clone());
+    assert!(typed_vars.check("{ uids(func: uid(0x1)) { uid } }").is_ok());
+    assert!(typed_vars.check("query all($b: int) { uids() {} }").is_ok());
+    assert_eq!(HashMap::from(typed_vars), vars);
+
+    let query = r#"query all($a: string){
+        uids(func: uid($a)) {
+            uid
+        }
+    }"#;
+    let json: UidJson = dgraph
+        .new_readonly_txn()
+        .unwrap()
+        .query_with_vars_as(query, vars)
+        .unwrap();
+
+    assert_eq!(json.uids[0].uid, "0x1");
+}
+
 #[test]
 fn it_runs_multiple_queries_in_a_single_transaction() {
     let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
@@ -88,7 +159,7 @@ fn it_runs_multiple_queries_in_a_single_transaction() {
         }}"#,
         uid
     );
-    let mut txn = dgraph.new_readonly_txn();
+    let mut txn = dgraph.new_readonly_txn().unwrap();
     let resp1 = txn.query(&query);
     let resp2 = txn.query(&query);
 
@@ -100,7 +171,7 @@ fn it_runs_multiple_queries_in_a_single_transaction() {
 fn it_commits_a_mutation() {
     let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
 
-    let mut txn = dgraph.new_txn();
+    let mut txn = dgraph.new_txn().unwrap();
     let mut mutation = dgraph::Mutation::new();
 
     mutation.set_set_json(br#"{"name": "Alice"}"#.to_vec());
@@ -114,7 +185,7 @@ fn it_commits_a_mutation() {
 fn it_returns_error_if_autocommited_mutation_is_commited_again() {
     let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
 
-    let mut txn = dgraph.new_txn();
+    let mut txn = dgraph.new_txn().unwrap();
     let mut mutation = dgraph::Mutation {
         commit_now: true,
         ..Default::default()
@@ -135,7 +206,7 @@ fn it_returns_error_if_autocommited_mutation_is_commited_again() {
 fn it_does_not_allow_mutation_in_readonly_transaction() {
     let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
 
-    let mut txn = dgraph.new_readonly_txn();
+    let mut txn = dgraph.new_readonly_txn().unwrap();
     let mut mutation = dgraph::Mutation::new();
 
     mutation.set_set_json(br#"{"name": "Alice"}"#.to_vec());
@@ -152,7 +223,7 @@ fn it_does_not_allow_mutation_in_readonly_transaction() {
 // fn it_discards_a_transaction() {
 //     let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
 
-//     let mut txn = dgraph.new_txn();
+//     let mut txn = dgraph.new_txn().unwrap();
 //     let mut mutation = dgraph::Mutation::new();
 
 //     mutation.set_set_json(br#"{"name": "Alice"}"#.to_vec());
@@ -166,7 +237,7 @@ fn it_does_not_allow_mutation_in_readonly_transaction() {
 fn it_does_nothing_if_autocommited_mutation_is_discarded() {
     let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
 
-    let mut txn = dgraph.new_txn();
+    let mut txn = dgraph.new_txn().unwrap();
     let mut mutation = dgraph::Mutation {
         commit_now: true,
         ..Default::default()
@@ -183,7 +254,7 @@ fn it_does_nothing_if_autocommited_mutation_is_discarded() {
 fn it_does_not_commit_discarded_transaction() {
     let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
 
-    let mut txn = dgraph.new_txn();
+    let mut txn = dgraph.new_txn().unwrap();
     let mut mutation = dgraph::Mutation::new();
 
     mutation.set_set_json(br#"{"name": "Alice"}"#.to_vec());
@@ -217,7 +288,7 @@ fn it_runs_query_through_do_request() {
         ..Default::default()
     };
 
-    let resp = dgraph.new_readonly_txn().do_request(&mut request);
+    let resp = dgraph.new_readonly_txn().unwrap().do_request(&mut request);
     let json: UidJson = serde_json::from_slice(&resp.unwrap().json).unwrap();
 
     assert_eq!(json.uids[0].uid, uid);
@@ -227,7 +298,7 @@ fn it_runs_query_through_do_request() {
 fn it_runs_query_and_mutation_without_variables_through_do_request() {
     let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
 
-    let mut txn = dgraph.new_txn();
+    let mut txn = dgraph.new_txn().unwrap();
     let uid = "0x1";
     let query = format!(
         r#"{{
@@ -257,7 +328,7 @@ fn it_runs_query_and_mutation_without_variables_through_do_request() {
 fn it_runs_query_and_mutation_through_do_request() {
     let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
 
-    let mut txn = dgraph.new_txn();
+    let mut txn = dgraph.new_txn().unwrap();
     let uid = "0x1";
     let query = format!(
         r#"{{
@@ -282,3 +353,386 @@ fn it_runs_query_and_mutation_through_do_request() {
 
     assert!(result.is_ok());
 }
+
+#[test]
+fn it_returns_deadline_exceeded_when_txn_times_out() {
+    let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
+
+    let mut txn = dgraph
+        .new_readonly_txn()
+        .unwrap()
+        .with_timeout(std::time::Duration::from_nanos(1));
+    let result = txn.query("schema {}");
+
+    let error_matched = match result.unwrap_err() {
+        DgraphError::DeadlineExceeded => true,
+        _ => false,
+    };
+    assert!(error_matched);
+}
+
+#[test]
+fn it_runs_closure_in_managed_transaction() {
+    let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
+
+    let result = dgraph.run_in_txn(|txn| {
+        let mut mutation = dgraph::Mutation::new();
+        mutation.set_set_json(br#"{"name": "Alice"}"#.to_vec());
+
+        Ok(txn.mutate(mutation)?.uids.len())
+    });
+
+    assert_eq!(result.unwrap(), 1);
+}
+
+fn counter(uid: dgraph::Uid, email: &str, count: i64) -> serde_json::Value {
+    serde_json::json!({ "uid": uid.to_string(), "retry.email": email, "retry.count": count })
+}
+
+fn read_count(txn: &mut dgraph::Txn, uid: dgraph::Uid) -> Result<i64, DgraphError> {
+    let query = r#"query q($uid: string) { q(func: uid($uid)) { retry.count } }"#;
+    let nodes: Vec<serde_json::Value> = txn
+        .query_with_vars(query, dgraph::Vars::new().uid("uid", uid))?
+        .block_as("q")?;
+
+    Ok(nodes[0]["retry.count"].as_i64().unwrap())
+}
+
+/// Creates a counter node on an `@upsert` predicate, which concurrent writers conflict on.
+fn setup_counter(dgraph: &dgraph::Dgraph, name: &str) -> (dgraph::Uid, String) {
+    dgraph
+        .alter(&dgraph::Operation {
+            schema: "retry.email: string @index(exact) @upsert .\n\
+                     retry.count: int ."
+                .to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+
+    let email = format!("{}-{}@example.com", name, std::process::id());
+    let assigned = dgraph
+        .new_txn()
+        .unwrap()
+        .set_json_commit_now(&serde_json::json!({
+            "uid": "_:counter",
+            "retry.email": email,
+            "retry.count": 0,
+        }))
+        .unwrap();
+
+    (assigned.uid("counter").unwrap(), email)
+}
+
+#[test]
+fn it_retries_closure_aborted_by_a_conflicting_transaction() {
+    let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
+    let (uid, email) = setup_counter(&dgraph, "retried");
+
+    let mut attempts = 0;
+    let result = dgraph.run_in_txn(|txn| {
+        attempts += 1;
+        let count = read_count(txn, uid)?;
+
+        if attempts == 1 {
+            dgraph
+                .new_txn()?
+                .set_json_commit_now(&counter(uid, &email, 100))?;
+        }
+
+        txn.set_json(&counter(uid, &email, count + 1))?;
+        Ok(count + 1)
+    });
+
+    assert_eq!(result.unwrap(), 101);
+    assert_eq!(attempts, 2);
+    assert_eq!(
+        read_count(&mut dgraph.new_readonly_txn().unwrap(), uid).unwrap(),
+        101
+    );
+}
+
+#[test]
+fn it_returns_txn_aborted_once_retries_are_exhausted() {
+    let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
+    let (uid, email) = setup_counter(&dgraph, "exhausted");
+    let policy = dgraph::RetryPolicy::new(3).base_delay(std::time::Duration::from_millis(1));
+
+    let mut attempts = 0;
+    let result = dgraph.run_in_txn_with(&policy, |txn| {
+        attempts += 1;
+        let count = read_count(txn, uid)?;
+
+        dgraph
+            .new_txn()?
+            .set_json_commit_now(&counter(uid, &email, count + 100))?;
+
+        txn.set_json(&counter(uid, &email, count + 1))?;
+        Ok(())
+    });
+
+    let error_matched = match result.unwrap_err() {
+        DgraphError::TxnAborted => true,
+        _ => false,
+    };
+    assert!(error_matched);
+    assert_eq!(attempts, 3);
+    assert_eq!(
+        read_count(&mut dgraph.new_readonly_txn().unwrap(), uid).unwrap(),
+        300
+    );
+}
+
+#[test]
+fn it_returns_query_syntax_error_with_position() {
+    let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
+
+    let result = dgraph
+        .new_readonly_txn()
+        .unwrap()
+        .query("{\n  uids(func: uid(0x1) {\n    uid\n  }\n}");
+
+    let error_matched = match result.unwrap_err() {
+        DgraphError::QuerySyntax { line, .. } => line > 0,
+        _ => false,
+    };
+    assert!(error_matched);
+}
+
+#[test]
+fn it_commits_a_transaction_moved_into_another_thread() {
+    let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
+
+    let mut txn = dgraph.new_txn().unwrap();
+    let handle = std::thread::spawn(move || {
+        let mut mutation = dgraph::Mutation::new();
+        mutation.set_set_json(br#"{"name": "Alice"}"#.to_vec());
+        txn.mutate(mutation)?;
+        txn.commit()
+    });
+
+    assert!(handle.join().unwrap().is_ok());
+}
+
+#[test]
+fn it_deserializes_query_results() {
+    let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
+
+    let uid = "0x1";
+    let query = format!(
+        r#"{{
+            uids(func: uid({})) {{
+                uid,
+            }}
+        }}"#,
+        uid
+    );
+    let mut txn = dgraph.new_readonly_txn().unwrap();
+    let json: UidJson = txn.query_as(&query).unwrap();
+    let uids: Vec<Uid> = txn.query(&query).unwrap().block_as("uids").unwrap();
+
+    assert_eq!(json.uids[0].uid, uid);
+    assert_eq!(uids[0].uid, uid);
+}
+
+#[test]
+fn it_returns_deserialize_error_for_missing_block() {
+    let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
+
+    let resp = dgraph
+        .new_readonly_txn()
+        .unwrap()
+        .query("{ uids(func: uid(0x1)) { uid } }")
+        .unwrap();
+
+    let error_matched = match resp.block_as::<Vec<Uid>>("me").unwrap_err() {
+        DgraphError::Deserialize { json, .. } => json.contains("uids"),
+        _ => false,
+    };
+    assert!(error_matched);
+}
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct Person {
+    pub uid: String,
+    pub name: String,
+}
+
+#[test]
+fn it_sets_json_and_returns_assigned_uids() {
+    let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
+
+    let mut txn = dgraph.new_txn().unwrap();
+    let alice = Person {
+        uid: "_:alice".to_string(),
+        name: "Alice".to_string(),
+    };
+    let assigned = txn.set_json_commit_now(&alice).unwrap();
+
+    assert!(assigned.uid("_:alice").is_some());
+    assert!(assigned.context.start_ts > 0);
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TypedUid {
+    pub uid: dgraph::Uid,
+}
+
+#[test]
+fn it_queries_assigned_uids_back_as_typed_uids() {
+    let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
+
+    let alice = Person {
+        uid: "_:alice".to_string(),
+        name: "Alice".to_string(),
+    };
+    let assigned = dgraph
+        .new_txn()
+        .unwrap()
+        .set_json_commit_now(&alice)
+        .unwrap();
+    let uid = assigned.uid("alice").unwrap();
+
+    let query = r#"query all($a: string){
+        all(func: uid($a)) {
+            uid
+        }
+    }"#;
+    let all: Vec<TypedUid> = dgraph
+        .new_readonly_txn()
+        .unwrap()
+        .query_with_vars(query, dgraph::Vars::new().uid("$a", uid))
+        .unwrap()
+        .block_as("all")
+        .unwrap();
+
+    assert_eq!(all[0].uid, uid);
+}
+
+#[test]
+fn it_runs_conditional_upsert() {
+    let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
+    dgraph
+        .alter(&dgraph::Operation {
+            schema: "email: string @index(exact) @upsert .".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+
+    let email = format!("upsert-{}@example.com", std::process::id());
+    let upsert = || {
+        dgraph::Upsert::new(format!(r#"{{ v as var(func: eq(email, "{}")) }}"#, email))
+            .set_nquads(format!(r#"_:user <email> "{}" ."#, email))
+            .when("@if(eq(len(v), 0))")
+            .set_nquads(r#"uid(v) <name> "Upserted" ."#)
+            .when("@if(eq(len(v), 1))")
+            .commit_now()
+    };
+
+    let first = dgraph.new_txn().unwrap().upsert(upsert()).unwrap();
+    let second = dgraph.new_txn().unwrap().upsert(upsert()).unwrap();
+
+    assert_eq!(first.mutations().len(), 2);
+    assert!(first.mutations()[0].uid("user").is_some());
+    assert!(second.mutations()[0].uid("user").is_none());
+}
+
+#[test]
+fn it_ignores_blank_nodes_inside_upsert_literals() {
+    let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
+
+    let upsert = dgraph::Upsert::new("{ q(func: uid(0x1)) { uid } }")
+        .set_nquads(r#"_:quoted <name> "not a \"_:node\" nor _:other" ."#)
+        .commit_now();
+    let response = dgraph.new_txn().unwrap().upsert(upsert).unwrap();
+
+    let names: Vec<&String> = response.mutations()[0].uids.keys().collect();
+    assert_eq!(names, vec!["quoted"]);
+}
+
+#[test]
+fn it_rejects_upsert_condition_before_any_mutation() {
+    let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
+
+    let upsert = dgraph::Upsert::new("{ v as var(func: uid(0x1)) }")
+        .when("@if(eq(len(v), 0))")
+        .set_nquads(r#"_:guarded <name> "Guarded" ."#)
+        .commit_now();
+
+    let error_matched = match dgraph.new_txn().unwrap().upsert(upsert) {
+        Err(DgraphError::InvalidDql(_)) => true,
+        _ => false,
+    };
+    assert!(error_matched);
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Member {
+    pub uid: dgraph::Uid,
+    pub team: String,
+}
+
+#[test]
+fn it_paginates_query_results_at_one_snapshot() {
+    let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
+    dgraph
+        .alter(&dgraph::Operation {
+            schema: "team: string @index(exact) .".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+
+    let team = format!("paginated-{}", std::process::id());
+    let members: Vec<_> = (0..5)
+        .map(|_| serde_json::json!({ "team": team }))
+        .collect();
+    dgraph
+        .new_txn()
+        .unwrap()
+        .set_json_commit_now(&members)
+        .unwrap();
+
+    let query = r#"query members($team: string, $first: int, $after: string) {
+        members(func: eq(team, $team), first: $first, after: $after) { uid team }
+    }"#;
+    let mut txn = dgraph.new_readonly_txn().unwrap();
+    let mut pages = txn.paginate_with_vars::<Member>(
+        query,
+        dgraph::Vars::new().string("team", team.as_str()),
+        2,
+    );
+
+    let first = pages.next().unwrap().unwrap();
+    dgraph
+        .new_txn()
+        .unwrap()
+        .set_json_commit_now(&serde_json::json!({ "team": team }))
+        .unwrap();
+    let rest: Vec<Vec<Member>> = pages.collect::<Result<_, _>>().unwrap();
+
+    let sizes: Vec<usize> = std::iter::once(&first)
+        .chain(&rest)
+        .map(|page| page.len())
+        .collect();
+    assert_eq!(sizes, vec![2, 2, 1]);
+
+    let uids: Vec<dgraph::Uid> = std::iter::once(first)
+        .chain(rest)
+        .flatten()
+        .map(|member| member.uid)
+        .collect();
+    assert!(uids.windows(2).all(|pair| pair[0] < pair[1]));
+}
+
+#[test]
+fn it_rejects_paginated_queries_without_cursor_vars() {
+    let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
+
+    let mut txn = dgraph.new_readonly_txn().unwrap();
+    let mut pages = txn.paginate::<TypedUid>("{ uids(func: uid(0x1)) { uid } }", 10);
+
+    let error_matched = match pages.next() {
+        Some(Err(DgraphError::InvalidVars(_))) => true,
+        _ => false,
+    };
+    assert!(error_matched);
+}
@@ -0,0 +1,80 @@
+use dgraph::{make_dgraph, DgraphError, Operation};
+use futures::executor::block_on;
+use serde_derive::{Deserialize, Serialize};
+use serde_json;
+
+mod common;
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct UidJson {
+    pub uids: Vec<Uid>,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct Uid {
+    pub uid: String,
+}
+
+#[test]
+fn it_runs_simple_query_async() {
+    let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
+
+    let uid = "0x1";
+    let query = format!(
+        r#"{{
+            uids(func: uid({})) {{
+                uid,
+            }}
+        }}"#,
+        uid
+    );
+    let resp = block_on(dgraph.new_readonly_txn().unwrap().query_async(&query));
+    let json: UidJson = serde_json::from_slice(&resp.unwrap().json).unwrap();
+
+    assert_eq!(json.uids[0].uid, uid);
+}
+
+#[test]
+fn it_commits_a_mutation_async() {
+    let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
+
+    let result = block_on(async {
+        let mut txn = dgraph.new_txn().unwrap();
+        let mut mutation = dgraph::Mutation::new();
+
+        mutation.set_set_json(br#"{"name": "Alice"}"#.to_vec());
+        txn.mutate_async(mutation).await?;
+        txn.commit_async().await
+    });
+
+    assert!(result.is_ok());
+}
+
+#[test]
+fn it_does_not_allow_mutation_in_readonly_transaction_async() {
+    let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
+
+    let mut txn = dgraph.new_readonly_txn().unwrap();
+    let mut mutation = dgraph::Mutation::new();
+
+    mutation.set_set_json(br#"{"name": "Alice"}"#.to_vec());
+    let result = block_on(txn.mutate_async(mutation));
+
+    let error_matched = match result.unwrap_err() {
+        DgraphError::TxnReadOnly => true,
+        _ => false,
+    };
+    assert!(error_matched);
+}
+
+#[test]
+fn it_alters_schema_async() {
+    let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
+
+    let result = block_on(dgraph.alter_async(&Operation {
+        schema: "something: string .".to_string(),
+        ..Default::default()
+    }));
+
+    assert!(result.is_ok());
+}
@@ -0,0 +1,34 @@
+use dgraph::{DgraphError, Uid};
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Node {
+    uid: Uid,
+}
+
+#[test]
+fn it_parses_hex_and_decimal_uids() {
+    assert_eq!("0x1a".parse::<Uid>().unwrap(), Uid(26));
+    assert_eq!("26".parse::<Uid>().unwrap(), Uid(26));
+
+    match "_:alice".parse::<Uid>() {
+        Err(DgraphError::InvalidUid(uid)) => assert_eq!(uid, "_:alice"),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn it_displays_uids_as_hex() {
+    assert_eq!(Uid(26).to_string(), "0x1a");
+    assert_eq!(String::from(Uid(1)), "0x1");
+}
+
+#[test]
+fn it_round_trips_uids_through_json() {
+    let node: Node = serde_json::from_str(r#"{"uid": "0x2a"}"#).unwrap();
+    assert_eq!(node, Node { uid: Uid(42) });
+    assert_eq!(serde_json::to_string(&node).unwrap(), r#"{"uid":"0x2a"}"#);
+
+    let node: Node = serde_json::from_str(r#"{"uid": 42}"#).unwrap();
+    assert_eq!(node.uid, Uid(42));
+}
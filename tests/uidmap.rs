@@ -0,0 +1,113 @@
+use dgraph::loader::{Format, Loader};
+use dgraph::{make_dgraph, rdf, Uid, UidMap};
+use serde_derive::Deserialize;
+use serde_json::json;
+use std::fs;
+
+mod common;
+
+fn temp_file(name: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn it_rewrites_known_nodes_and_external_ids() {
+    let mut uids = UidMap::new();
+    uids.insert("alice", Uid(1)).unwrap();
+    uids.insert("team", Uid(2)).unwrap();
+
+    let mut nquads = rdf::parse(
+        "_:alice <member> _:team .\n\
+         <bob> <member> <team> .\n\
+         _:carol <friend> <0x5> .",
+    )
+    .unwrap();
+    uids.rewrite_nquads(&mut nquads);
+
+    let nodes: Vec<_> = nquads
+        .iter()
+        .map(|nquad| (nquad.subject.as_str(), nquad.object_id.as_str()))
+        .collect();
+    assert_eq!(
+        nodes,
+        vec![("0x1", "0x2"), ("_:bob", "0x2"), ("_:carol", "0x5")]
+    );
+
+    let mut value = json!({ "uid": "_:alice", "friend": [{ "uid": "_:dave" }, { "uid": "team" }] });
+    uids.rewrite_json(&mut value);
+    assert_eq!(
+        value,
+        json!({ "uid": "0x1", "friend": [{ "uid": "_:dave" }, { "uid": "0x2" }] })
+    );
+}
+
+#[test]
+fn it_persists_assignments() {
+    let path = temp_file("dgraph-uidmap");
+
+    {
+        let mut uids = UidMap::open(&path).unwrap();
+        uids.insert("alice", Uid(1)).unwrap();
+        uids.insert("odd\tname", Uid(0x2a)).unwrap();
+    }
+
+    let mut uids = UidMap::open(&path).unwrap();
+    assert_eq!(uids.len(), 2);
+    assert_eq!(uids.get("alice"), Some(Uid(1)));
+    assert_eq!(uids.get("odd\tname"), Some(Uid(0x2a)));
+
+    uids.insert("alice", Uid(1)).unwrap();
+    uids.flush().unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 2);
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[derive(Deserialize)]
+struct Count {
+    count: usize,
+}
+
+#[test]
+fn it_makes_reloads_idempotent() {
+    let dgraph = make_dgraph!(dgraph::new_dgraph_client(common::DGRAPH_URL));
+    dgraph
+        .alter(&dgraph::Operation {
+            schema: "UidMap.name: string @index(exact) .".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+
+    let path = temp_file("dgraph-uidmap-load");
+    // Names are unique per run, since earlier runs leave their nodes behind.
+    let run = std::process::id();
+    let rdf = format!(
+        "<uidmap-ann> <UidMap.name> \"Ann {0}\" .\n_:ben <UidMap.name> \"Ben {0}\" .\n",
+        run
+    );
+
+    for _ in 0..2 {
+        Loader::new()
+            .batch_size(1)
+            .uid_map(UidMap::open(&path).unwrap())
+            .load(&dgraph, rdf.as_bytes(), Format::Rdf)
+            .unwrap();
+    }
+
+    let counts: Vec<Count> = dgraph
+        .new_readonly_txn()
+        .unwrap()
+        .query(r#"{ q(func: eq(UidMap.name, ["UidMap Ann", "UidMap Ben"])) { count(uid) } }"#)
+        .unwrap()
+        .block_as("q")
+        .unwrap();
+    assert_eq!(counts[0].count, 2);
+
+    let uids = UidMap::open(&path).unwrap();
+    assert!(uids.get("uidmap-ann").is_some());
+    assert!(uids.get("ben").is_some());
+
+    fs::remove_file(&path).unwrap();
+}